@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use chess::board::Board;
+use chess::fen::STARTING_FEN;
+
+const USAGE: &str = "usage: perft <depth> [<fen>] [--expected <file>]
+
+Prints the number of positions reached after each legal move, as in
+`go perft` in other engines, then the total and the nodes per second.
+The FEN may be given as one argument or as its separate fields, and
+defaults to the starting position.
+
+With --expected, the counts are compared against a file in the same
+\"<move>: <nodes>\" format, and any differences are listed.";
+
+struct Args {
+    depth: u32,
+    fen: String,
+    expected: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut depth = None;
+    let mut fen_fields = vec![];
+    let mut expected = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Err(String::new()),
+            "--expected" => match args.next() {
+                Some(file) => expected = Some(file),
+                None => return Err("--expected needs a file name".to_owned()),
+            },
+            _ if depth.is_none() => match arg.parse() {
+                Ok(0) => return Err("the depth has to be at least 1".to_owned()),
+                Ok(parsed) => depth = Some(parsed),
+                Err(_) => return Err(format!("\"{}\" is not a depth", arg)),
+            },
+            _ => fen_fields.push(arg),
+        }
+    }
+
+    let fen = if fen_fields.is_empty() {
+        STARTING_FEN.to_owned()
+    } else {
+        fen_fields.join(" ")
+    };
+
+    Ok(Args {
+        depth: depth.ok_or("missing depth")?,
+        fen,
+        expected,
+    })
+}
+
+/// Reads "<move>: <nodes>" lines, skipping anything else, such as a total.
+fn parse_counts(text: &str) -> BTreeMap<String, u64> {
+    text.lines()
+        .filter_map(|line| {
+            let (mv, nodes) = line.split_once(':')?;
+            let mv = mv.trim();
+
+            if mv.len() < 4 || mv.len() > 5 || mv.contains(' ') {
+                return None;
+            }
+
+            Some((mv.to_owned(), nodes.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Describes every move whose count differs between `actual` and `expected`.
+fn differences(actual: &BTreeMap<String, u64>, expected: &BTreeMap<String, u64>) -> Vec<String> {
+    let mut differences = vec![];
+
+    for (mv, nodes) in actual {
+        match expected.get(mv) {
+            Some(expected) if expected == nodes => (),
+            Some(expected) => differences.push(format!(
+                "{}: {} nodes, expected {} ({:+})",
+                mv,
+                nodes,
+                expected,
+                *nodes as i64 - *expected as i64
+            )),
+            None => differences.push(format!("{}: generated but not expected", mv)),
+        }
+    }
+
+    for mv in expected.keys() {
+        if !actual.contains_key(mv) {
+            differences.push(format!("{}: expected but not generated", mv));
+        }
+    }
+
+    differences
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            if !message.is_empty() {
+                eprintln!("perft: {}\n", message);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let board = match Board::from_fen(&args.fen) {
+        Ok(board) => board,
+        Err(error) => {
+            eprintln!("perft: invalid FEN: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+
+    let expected = match &args.expected {
+        Some(file) => match std::fs::read_to_string(file) {
+            Ok(text) => Some(parse_counts(&text)),
+            Err(error) => {
+                eprintln!("perft: could not read {}: {}", file, error);
+                return ExitCode::from(2);
+            }
+        },
+        None => None,
+    };
+
+    let start = Instant::now();
+    let divide = board.divide(args.depth);
+    let elapsed = start.elapsed();
+
+    let total: u64 = divide.iter().map(|(_, nodes)| nodes).sum();
+
+    for (mv, nodes) in &divide {
+        println!("{}: {}", mv, nodes);
+    }
+
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {:.3}s", elapsed.as_secs_f64());
+    println!(
+        "Nodes per second: {:.0}",
+        total as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+
+    if let Some(expected) = expected {
+        let actual = divide
+            .iter()
+            .map(|(mv, nodes)| (mv.to_uci(), *nodes))
+            .collect();
+        let differences = differences(&actual, &expected);
+
+        println!();
+        if differences.is_empty() {
+            println!("All counts match the expected ones.");
+        } else {
+            println!("{} moves differ:", differences.len());
+            for difference in differences {
+                println!("  {}", difference);
+            }
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn arguments() {
+        let parsed = args(&["3"]).unwrap();
+        assert_eq!(parsed.depth, 3);
+        assert_eq!(parsed.fen, STARTING_FEN);
+        assert_eq!(parsed.expected, None);
+
+        let parsed = args(&[
+            "2",
+            "8/8/8/8/8/8/8/K6k",
+            "w",
+            "-",
+            "-",
+            "--expected",
+            "counts",
+        ])
+        .unwrap();
+        assert_eq!(parsed.fen, "8/8/8/8/8/8/8/K6k w - -");
+        assert_eq!(parsed.expected, Some("counts".to_owned()));
+
+        let parsed = args(&["--expected", "counts", "1", STARTING_FEN]).unwrap();
+        assert_eq!(parsed.depth, 1);
+        assert_eq!(parsed.fen, STARTING_FEN);
+
+        assert!(args(&[]).is_err());
+        assert!(args(&["deep"]).is_err());
+        assert!(args(&["0"]).is_err());
+        assert!(args(&["1", "--expected"]).is_err());
+    }
+
+    #[test]
+    fn compare_counts() {
+        let expected = parse_counts("a2a3: 20\nb7b8q: 5\n\nNodes searched: 25\n");
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected.get("b7b8q"), Some(&5));
+
+        let actual = parse_counts("a2a3: 21\ne2e4: 20\n");
+        assert_eq!(
+            differences(&actual, &expected),
+            vec![
+                "a2a3: 21 nodes, expected 20 (+1)",
+                "e2e4: generated but not expected",
+                "b7b8q: expected but not generated",
+            ]
+        );
+        assert!(differences(&expected, &expected).is_empty());
+    }
+}
@@ -0,0 +1,461 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, Not};
+
+use crate::magic;
+use crate::piece::{Color, Piece, PieceKind};
+use crate::position::Position;
+
+/// A set of squares, one bit per square. Bit `n` is the square with `Position::index` `n`,
+/// so a1 is the lowest bit and h8 the highest.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Bitboard(pub u64);
+
+pub(crate) const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const ALL: Bitboard = Bitboard(u64::MAX);
+
+    /// The board holding just `position`, or nothing if it is off the board.
+    pub const fn from_position(position: Position) -> Self {
+        match position.index() {
+            Some(index) => Bitboard(1 << index),
+            None => Bitboard::EMPTY,
+        }
+    }
+
+    pub const fn contains(self, position: Position) -> bool {
+        self.0 & Bitboard::from_position(position).0 != 0
+    }
+
+    pub const fn with(self, position: Position) -> Self {
+        Bitboard(self.0 | Bitboard::from_position(position).0)
+    }
+
+    pub const fn without(self, position: Position) -> Self {
+        Bitboard(self.0 & !Bitboard::from_position(position).0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// The lowest square in the set.
+    pub const fn first(self) -> Option<Position> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Position::from_index(self.0.trailing_zeros() as u8))
+        }
+    }
+}
+
+impl Iterator for Bitboard {
+    type Item = Position;
+
+    /// Takes squares out of the set from a1 towards h8.
+    fn next(&mut self) -> Option<Position> {
+        let first = self.first()?;
+        self.0 &= self.0 - 1;
+        Some(first)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.count() as usize;
+        (count, Some(count))
+    }
+}
+
+impl FromIterator<Position> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Position>>(positions: I) -> Self {
+        positions
+            .into_iter()
+            .fold(Bitboard::EMPTY, |bitboard, position| {
+                bitboard.with(position)
+            })
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Bitboard;
+
+    fn bitand(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Bitboard) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Bitboard;
+
+    fn bitor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Bitboard) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Bitboard;
+
+    fn bitxor(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 ^ rhs.0)
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+/// Shifts every square of `bits` by `dx` files and `dy` ranks, dropping the ones that
+/// would leave the board.
+pub(crate) const fn shift(bits: u64, dx: i8, dy: i8) -> u64 {
+    let mut bits = bits;
+    let mut x = dx;
+    while x > 0 {
+        bits = (bits & !FILE_H) << 1;
+        x -= 1;
+    }
+    while x < 0 {
+        bits = (bits & !FILE_A) >> 1;
+        x += 1;
+    }
+
+    if dy > 0 {
+        bits << (8 * dy as u32)
+    } else {
+        bits >> (8 * -dy as u32)
+    }
+}
+
+const fn step_table<const N: usize>(steps: [(i8, i8); N]) -> [Bitboard; 64] {
+    let mut table = [Bitboard::EMPTY; 64];
+    let mut index = 0;
+    while index < 64 {
+        let mut attacks = 0;
+        let mut step = 0;
+        while step < steps.len() {
+            attacks |= shift(1 << index, steps[step].0, steps[step].1);
+            step += 1;
+        }
+        table[index] = Bitboard(attacks);
+        index += 1;
+    }
+    table
+}
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = step_table([
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+]);
+
+const KING_ATTACKS: [Bitboard; 64] = step_table([
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+]);
+
+const WHITE_PAWN_ATTACKS: [Bitboard; 64] = step_table([(-1, 1), (1, 1)]);
+
+const BLACK_PAWN_ATTACKS: [Bitboard; 64] = step_table([(-1, -1), (1, -1)]);
+
+pub(crate) const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+pub(crate) const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+fn table_lookup(table: &[Bitboard; 64], position: Position) -> Bitboard {
+    position
+        .index()
+        .map_or(Bitboard::EMPTY, |index| table[index as usize])
+}
+
+pub fn knight_attacks(position: Position) -> Bitboard {
+    table_lookup(&KNIGHT_ATTACKS, position)
+}
+
+pub fn king_attacks(position: Position) -> Bitboard {
+    table_lookup(&KING_ATTACKS, position)
+}
+
+/// The squares a pawn of `color` standing on `position` attacks diagonally.
+pub fn pawn_attacks(color: Color, position: Position) -> Bitboard {
+    match color {
+        Color::White => table_lookup(&WHITE_PAWN_ATTACKS, position),
+        Color::Black => table_lookup(&BLACK_PAWN_ATTACKS, position),
+    }
+}
+
+/// Walks each direction from `position` up to and including the first occupied square.
+/// This is the slow way round, used to build the magic tables.
+pub(crate) fn ray_attacks(
+    position: Position,
+    occupied: Bitboard,
+    directions: [(i8, i8); 4],
+) -> Bitboard {
+    let mut attacks = 0;
+
+    for (dx, dy) in directions {
+        let mut square = Bitboard::from_position(position).0;
+
+        loop {
+            square = shift(square, dx, dy);
+            attacks |= square;
+
+            if square == 0 || square & occupied.0 != 0 {
+                break;
+            }
+        }
+    }
+
+    Bitboard(attacks)
+}
+
+pub fn bishop_attacks(position: Position, occupied: Bitboard) -> Bitboard {
+    magic::bishop_attacks(position, occupied)
+}
+
+pub fn rook_attacks(position: Position, occupied: Bitboard) -> Bitboard {
+    magic::rook_attacks(position, occupied)
+}
+
+pub fn queen_attacks(position: Position, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(position, occupied) | rook_attacks(position, occupied)
+}
+
+pub(crate) const fn kind_index(kind: PieceKind) -> usize {
+    match kind {
+        PieceKind::Pawn => 0,
+        PieceKind::Knight => 1,
+        PieceKind::Bishop => 2,
+        PieceKind::Rook => 3,
+        PieceKind::Queen => 4,
+        PieceKind::King => 5,
+    }
+}
+
+pub(crate) const fn color_index(color: Color) -> usize {
+    match color {
+        Color::Black => 0,
+        Color::White => 1,
+    }
+}
+
+/// Where the pieces are, as one bitboard per piece kind and one per color.
+/// Answers the same questions about the pieces as `Board` without going through
+/// the `Piece` trait objects.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Bitboards {
+    kinds: [Bitboard; 6],
+    colors: [Bitboard; 2],
+}
+
+impl Bitboards {
+    pub fn new(pieces: &[Box<dyn Piece>]) -> Self {
+        let mut bitboards = Bitboards::default();
+
+        for piece in pieces {
+            bitboards.insert(piece.kind(), piece.color(), piece.position());
+        }
+
+        bitboards
+    }
+
+    pub fn insert(&mut self, kind: PieceKind, color: Color, position: Position) {
+        self.remove(position);
+
+        let square = Bitboard::from_position(position);
+        self.kinds[kind_index(kind)] |= square;
+        self.colors[color_index(color)] |= square;
+    }
+
+    /// Takes whatever piece is on `position` off the board.
+    pub fn remove(&mut self, position: Position) {
+        let others = !Bitboard::from_position(position);
+
+        for bitboard in self.kinds.iter_mut().chain(self.colors.iter_mut()) {
+            *bitboard &= others;
+        }
+    }
+
+    pub fn kind(&self, kind: PieceKind) -> Bitboard {
+        self.kinds[kind_index(kind)]
+    }
+
+    pub fn color(&self, color: Color) -> Bitboard {
+        self.colors[color_index(color)]
+    }
+
+    pub fn pieces(&self, kind: PieceKind, color: Color) -> Bitboard {
+        self.kind(kind) & self.color(color)
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    pub fn piece_at(&self, position: Position) -> Option<(PieceKind, Color)> {
+        let color = [Color::Black, Color::White]
+            .into_iter()
+            .find(|color| self.color(*color).contains(position))?;
+
+        PieceKind::ALL
+            .into_iter()
+            .find(|kind| self.kind(*kind).contains(position))
+            .map(|kind| (kind, color))
+    }
+
+    pub fn king_position(&self, color: Color) -> Option<Position> {
+        self.pieces(PieceKind::King, color).first()
+    }
+
+    /// The pieces of color `by` that attack `position`.
+    pub fn attackers(&self, position: Position, by: Color) -> Bitboard {
+        let occupied = self.occupied();
+        let queens = self.pieces(PieceKind::Queen, by);
+
+        (knight_attacks(position) & self.pieces(PieceKind::Knight, by))
+            | (king_attacks(position) & self.pieces(PieceKind::King, by))
+            // a pawn attacks `position` from where a pawn of the other color on
+            // `position` would attack
+            | (pawn_attacks(by.opposite(), position) & self.pieces(PieceKind::Pawn, by))
+            | (bishop_attacks(position, occupied) & (self.pieces(PieceKind::Bishop, by) | queens))
+            | (rook_attacks(position, occupied) & (self.pieces(PieceKind::Rook, by) | queens))
+    }
+
+    /// Whether any piece of color `by` attacks `position`.
+    pub fn is_attacked(&self, position: Position, by: Color) -> bool {
+        !self.attackers(position, by).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn squares(names: &[&str]) -> Bitboard {
+        names.iter().map(|name| name.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn bitboard() {
+        let e4: Position = "e4".parse().unwrap();
+        let bitboard = Bitboard::from_position(e4);
+
+        assert_eq!(bitboard, Bitboard(1 << 28));
+        assert!(bitboard.contains(e4));
+        assert!(!bitboard.contains("e5".parse().unwrap()));
+        assert_eq!(bitboard.without(e4), Bitboard::EMPTY);
+        assert_eq!(
+            Bitboard::from_position(Position::new(8, 0)),
+            Bitboard::EMPTY
+        );
+
+        let bitboard = squares(&["h8", "a1", "e4"]);
+        assert_eq!(bitboard.count(), 3);
+        assert_eq!(
+            bitboard.collect::<Vec<Position>>(),
+            vec!["a1".parse().unwrap(), e4, "h8".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn attack_tables() {
+        let attacks = |bitboard: Bitboard| {
+            let mut names: Vec<String> = bitboard.map(|position| position.to_string()).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            attacks(knight_attacks("a1".parse().unwrap())),
+            vec!["b3", "c2"]
+        );
+        assert_eq!(knight_attacks("e4".parse().unwrap()).count(), 8);
+        assert_eq!(
+            attacks(king_attacks("h8".parse().unwrap())),
+            vec!["g7", "g8", "h7"]
+        );
+        assert_eq!(
+            attacks(pawn_attacks(Color::White, "a2".parse().unwrap())),
+            vec!["b3"]
+        );
+        assert_eq!(
+            attacks(pawn_attacks(Color::Black, "e7".parse().unwrap())),
+            vec!["d6", "f6"]
+        );
+
+        let occupied = squares(&["e6", "c4", "g2"]);
+        assert_eq!(
+            attacks(rook_attacks("e4".parse().unwrap(), occupied)),
+            vec!["c4", "d4", "e1", "e2", "e3", "e5", "e6", "f4", "g4", "h4"]
+        );
+        assert_eq!(
+            attacks(bishop_attacks("e4".parse().unwrap(), occupied)),
+            vec!["a8", "b1", "b7", "c2", "c6", "d3", "d5", "f3", "f5", "g2", "g6", "h7"]
+        );
+    }
+
+    #[test]
+    fn matches_board() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let bitboards = Bitboards::new(board.pieces());
+
+        assert_eq!(bitboards.occupied().count(), 32);
+        assert_eq!(bitboards.pieces(PieceKind::Pawn, Color::White).count(), 8);
+        assert_eq!(
+            bitboards.king_position(Color::Black),
+            Some("e8".parse().unwrap())
+        );
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let position = Position::new(x, y);
+                assert_eq!(
+                    bitboards.piece_at(position),
+                    board
+                        .piece_at(position)
+                        .map(|piece| (piece.kind(), piece.color()))
+                );
+
+                for color in [Color::White, Color::Black] {
+                    let attacked = board
+                        .pieces()
+                        .iter()
+                        .filter(|piece| piece.color() == color)
+                        .any(|piece| piece.attacks(&board).contains(&position));
+                    assert_eq!(bitboards.is_attacked(position, color), attacked);
+                }
+            }
+        }
+    }
+}
@@ -1,25 +1,519 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 
-use crate::piece::{Bishop, Color, King, Knight, Pawn, Piece, Queen, Rook};
+use crate::bitboard::Bitboards;
+use crate::moves::{Move, MoveKind};
+use crate::piece::{new_piece, Bishop, Color, King, Knight, Pawn, Piece, PieceKind, Queen, Rook};
 use crate::position::{Position, XY};
+use crate::zobrist;
 
+#[derive(Clone)]
 pub struct Board {
     pieces: Vec<Box<dyn Piece>>,
+    /// The same pieces as `pieces`, kept in step with it for fast lookups.
+    bitboards: Bitboards,
+    side_to_move: Color,
+    castling_rights: CastlingRights,
+    en_passant: Option<Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// Zobrist hash of everything above except the clocks, updated as moves are made.
+    hash: u64,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+impl CastlingRights {
+    pub const fn all() -> Self {
+        Self {
+            white_king_side: true,
+            white_queen_side: true,
+            black_king_side: true,
+            black_queen_side: true,
+        }
+    }
+
+    pub const fn none() -> Self {
+        Self {
+            white_king_side: false,
+            white_queen_side: false,
+            black_king_side: false,
+            black_queen_side: false,
+        }
+    }
+
+    pub const fn king_side(&self, color: Color) -> bool {
+        match color {
+            Color::Black => self.black_king_side,
+            Color::White => self.white_king_side,
+        }
+    }
+
+    pub const fn queen_side(&self, color: Color) -> bool {
+        match color {
+            Color::Black => self.black_queen_side,
+            Color::White => self.white_queen_side,
+        }
+    }
+
+    /// Drops whatever rights are tied to a piece leaving or being taken on `position`.
+    fn remove_for(&mut self, position: Position) {
+        match position.to_xy() {
+            XY::OnBoard(4, 0) => {
+                self.white_king_side = false;
+                self.white_queen_side = false;
+            }
+            XY::OnBoard(4, 7) => {
+                self.black_king_side = false;
+                self.black_queen_side = false;
+            }
+            XY::OnBoard(7, 0) => self.white_king_side = false,
+            XY::OnBoard(0, 0) => self.white_queen_side = false,
+            XY::OnBoard(7, 7) => self.black_king_side = false,
+            XY::OnBoard(0, 7) => self.black_queen_side = false,
+            _ => (),
+        }
+    }
 }
 
 impl Board {
     pub fn new(pieces: Vec<Box<dyn Piece>>) -> Self {
-        Board { pieces }
+        let mut board = Board {
+            bitboards: Bitboards::new(&pieces),
+            pieces,
+            side_to_move: Color::White,
+            castling_rights: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+        };
+        board.hash = zobrist::hash(&board);
+
+        board
     }
 
     pub fn empty() -> Self {
-        Board { pieces: vec![] }
+        Board::new(vec![])
     }
 
     pub fn pieces(&self) -> &[Box<dyn Piece>] {
         &self.pieces
     }
+
+    pub fn bitboards(&self) -> &Bitboards {
+        &self.bitboards
+    }
+
+    pub fn piece_at(&self, position: Position) -> Option<&dyn Piece> {
+        if !self.bitboards.occupied().contains(position) {
+            return None;
+        }
+
+        self.pieces
+            .iter()
+            .find(|piece| piece.position() == position)
+            .map(|piece| piece.as_ref())
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    pub fn set_side_to_move(&mut self, side_to_move: Color) {
+        self.side_to_move = side_to_move;
+        self.hash = zobrist::hash(self);
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    pub fn set_castling_rights(&mut self, castling_rights: CastlingRights) {
+        self.castling_rights = castling_rights;
+        self.hash = zobrist::hash(self);
+    }
+
+    /// The square a pawn passed over on the previous move, if it was a double push.
+    pub fn en_passant(&self) -> Option<Position> {
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self, en_passant: Option<Position>) {
+        self.en_passant = en_passant;
+        self.hash = zobrist::hash(self);
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn set_halfmove_clock(&mut self, halfmove_clock: u32) {
+        self.halfmove_clock = halfmove_clock;
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn set_fullmove_number(&mut self, fullmove_number: u32) {
+        self.fullmove_number = fullmove_number;
+    }
+
+    /// A Zobrist hash of the piece placement, side to move, castling rights and
+    /// en passant file. The en passant file only counts when a legal capture there is
+    /// possible.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn king_position(&self, color: Color) -> Option<Position> {
+        self.bitboards.king_position(color)
+    }
+
+    /// Whether any piece of color `by` attacks `position`.
+    pub fn is_attacked(&self, position: Position, by: Color) -> bool {
+        self.bitboards.is_attacked(position, by)
+    }
+
+    /// All legal moves for the side to move.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut scratch = self.clone();
+
+        self.pieces
+            .iter()
+            .filter(|piece| piece.color() == self.side_to_move)
+            .flat_map(|piece| piece.moves(self))
+            .filter(|mv| !scratch.leaves_king_attacked(*mv))
+            .collect()
+    }
+
+    /// Whether the side to move has its king attacked.
+    pub fn is_check(&self) -> bool {
+        self.king_position(self.side_to_move)
+            .is_some_and(|king| self.is_attacked(king, self.side_to_move.opposite()))
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && !self.has_legal_moves()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && !self.has_legal_moves()
+    }
+
+    /// Whether neither side has the material left to ever give checkmate: bare kings,
+    /// a lone bishop or knight against a bare king, or bishops that all stand on squares
+    /// of the same color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let material: Vec<&dyn Piece> = self
+            .pieces
+            .iter()
+            .map(|piece| piece.as_ref())
+            .filter(|piece| piece.kind() != PieceKind::King)
+            .collect();
+
+        match material.as_slice() {
+            [] => true,
+            [piece] => matches!(piece.kind(), PieceKind::Bishop | PieceKind::Knight),
+            [first, ..] => material.iter().all(|piece| {
+                piece.kind() == PieceKind::Bishop
+                    && is_light_square(piece.position()) == is_light_square(first.position())
+            }),
+        }
+    }
+
+    /// Whether no sequence of legal moves can end in checkmate, which is the rule the
+    /// insufficient material draws are an instance of. This also catches positions such
+    /// as locked pawn chains where the kings can only walk around: every position that
+    /// can be reached without a capture or pawn move is visited, and the position is dead
+    /// if none of them is checkmate or allows a capture or pawn move.
+    /// Gives up and returns `false` if there are too many positions to visit, so a
+    /// position is never called dead when mate is possible.
+    pub fn is_dead_position(&self) -> bool {
+        const LIMIT: usize = 10_000;
+
+        if self.has_insufficient_material() {
+            return true;
+        }
+
+        let mut seen = HashSet::from([self.hash]);
+        let mut pending = vec![self.clone()];
+
+        while let Some(board) = pending.pop() {
+            let moves = board.legal_moves();
+
+            if moves.is_empty() && board.is_check() {
+                return false;
+            }
+
+            for mv in moves {
+                let mut next = board.clone();
+                next.make_move(mv);
+
+                if next.halfmove_clock == 0 {
+                    return false;
+                }
+
+                if seen.insert(next.hash) {
+                    if seen.len() > LIMIT {
+                        return false;
+                    }
+                    pending.push(next);
+                }
+            }
+        }
+
+        true
+    }
+
+    fn has_legal_moves(&self) -> bool {
+        let mut scratch = self.clone();
+
+        self.pieces
+            .iter()
+            .filter(|piece| piece.color() == self.side_to_move)
+            .flat_map(|piece| piece.moves(self))
+            .any(|mv| !scratch.leaves_king_attacked(mv))
+    }
+
+    /// Looks up the legal move going from `from` to `to`, with all of its details filled in.
+    /// `promotion` has to be given exactly when a pawn reaches the back rank.
+    pub fn legal_move(
+        &self,
+        from: Position,
+        to: Position,
+        promotion: Option<PieceKind>,
+    ) -> Option<Move> {
+        let piece = self
+            .piece_at(from)
+            .filter(|piece| piece.color() == self.side_to_move)?;
+
+        piece
+            .moves(self)
+            .into_iter()
+            .find(|mv| mv.to() == to && mv.promotion() == promotion)
+            .filter(|mv| !self.clone().leaves_king_attacked(*mv))
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        match self.piece_at(mv.from()) {
+            Some(piece) => {
+                piece.color() == self.side_to_move
+                    && piece.moves(self).contains(&mv)
+                    && !self.clone().leaves_king_attacked(mv)
+            }
+            None => false,
+        }
+    }
+
+    /// Tries `mv` out and takes it back again, leaving the board as it was.
+    fn leaves_king_attacked(&mut self, mv: Move) -> bool {
+        let color = self.side_to_move;
+        let undo = self.make_move(mv);
+
+        let attacked = self
+            .king_position(color)
+            .is_some_and(|king| self.is_attacked(king, color.opposite()));
+
+        self.unmake_move(undo);
+
+        attacked
+    }
+
+    /// Plays `mv` and updates the rest of the position state, returning what
+    /// `unmake_move` needs to take the move back.
+    /// Does not check the move for legality; callers are expected to have done so.
+    pub fn make_move(&mut self, mv: Move) -> Undo {
+        let (from, to) = (mv.from(), mv.to());
+
+        let mut index = self
+            .pieces
+            .iter()
+            .position(|piece| piece.position() == from)
+            .expect("no piece on the square being moved from");
+        let (kind, color) = (self.pieces[index].kind(), self.pieces[index].color());
+
+        let undo = Undo {
+            mv,
+            moved: kind,
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+
+        self.hash ^= zobrist::castling(self.castling_rights) ^ zobrist::en_passant(self);
+
+        // pieces are removed without reordering the rest, so that unmaking the move
+        // can put them back exactly where they were
+        let captured_position = captured_position(mv);
+        let captured = self
+            .pieces
+            .iter()
+            .position(|piece| piece.position() == captured_position)
+            .map(|captured_index| {
+                if captured_index < index {
+                    index -= 1;
+                }
+                let kind = self.pieces.remove(captured_index).kind();
+                self.hash ^= zobrist::piece(kind, color.opposite(), captured_position);
+                (captured_index, kind)
+            });
+        self.bitboards.remove(captured_position);
+
+        if let Some((rook_from, rook_to)) = rook_squares(mv) {
+            self.move_piece(rook_from, rook_to);
+        }
+
+        self.en_passant = match (mv.kind(), from.to_xy(), to.to_xy()) {
+            (MoveKind::DoublePawnPush, XY::OnBoard(x, from_y), XY::OnBoard(_, to_y)) => {
+                Some(Position::new(x, (from_y + to_y) / 2))
+            }
+            _ => None,
+        };
+
+        if kind == PieceKind::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        self.castling_rights.remove_for(from);
+        self.castling_rights.remove_for(to);
+
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+
+        let promoted = mv.promotion().unwrap_or(kind);
+        self.bitboards.remove(from);
+        self.bitboards.insert(promoted, color, to);
+        self.pieces[index] = new_piece(promoted, color, to);
+
+        self.hash ^= zobrist::piece(kind, color, from)
+            ^ zobrist::piece(promoted, color, to)
+            ^ zobrist::side_to_move(Color::Black)
+            ^ zobrist::castling(self.castling_rights)
+            ^ zobrist::en_passant(self);
+
+        Undo { captured, ..undo }
+    }
+
+    /// Takes back the move `undo` was returned for, which has to be the last move made.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let mv = undo.mv;
+
+        let index = self
+            .pieces
+            .iter()
+            .position(|piece| piece.position() == mv.to())
+            .expect("no piece on the square the move went to");
+        let color = self.pieces[index].color();
+
+        self.bitboards.remove(mv.to());
+        self.bitboards.insert(undo.moved, color, mv.from());
+        self.pieces[index] = new_piece(undo.moved, color, mv.from());
+
+        if let Some((rook_from, rook_to)) = rook_squares(mv) {
+            self.move_piece(rook_to, rook_from);
+        }
+
+        if let Some((captured_index, kind)) = undo.captured {
+            let position = captured_position(mv);
+            self.bitboards.insert(kind, color.opposite(), position);
+            self.pieces
+                .insert(captured_index, new_piece(kind, color.opposite(), position));
+        }
+
+        self.side_to_move = color;
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+    }
+
+    /// `make_move` for when the move will never be taken back.
+    pub(crate) fn play(&mut self, mv: Move) {
+        self.make_move(mv);
+    }
+
+    /// Moves whatever stands on `from` to `to`, keeping its place in `pieces`.
+    fn move_piece(&mut self, from: Position, to: Position) {
+        if let Some(piece) = self
+            .pieces
+            .iter_mut()
+            .find(|piece| piece.position() == from)
+        {
+            self.bitboards.remove(from);
+            self.bitboards.insert(piece.kind(), piece.color(), to);
+            self.hash ^= zobrist::piece(piece.kind(), piece.color(), from)
+                ^ zobrist::piece(piece.kind(), piece.color(), to);
+            *piece = new_piece(piece.kind(), piece.color(), to);
+        }
+    }
+}
+
+/// Everything about the position that a move changes and that cannot be worked out
+/// from the move itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Undo {
+    mv: Move,
+    /// The kind of the piece that moved, which is a pawn when the move promotes.
+    moved: PieceKind,
+    /// Where the captured piece was in `Board::pieces`, and its kind.
+    captured: Option<(usize, PieceKind)>,
+    castling_rights: CastlingRights,
+    en_passant: Option<Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+}
+
+impl Undo {
+    pub fn mv(&self) -> Move {
+        self.mv
+    }
+}
+
+/// The square of the piece `mv` takes: its destination, except for a pawn taking
+/// en passant, which lands behind the pawn it captures.
+fn captured_position(mv: Move) -> Position {
+    match (mv.kind(), mv.to().to_xy(), mv.from().to_xy()) {
+        (MoveKind::EnPassant, XY::OnBoard(x, _), XY::OnBoard(_, y)) => Position::new(x, y),
+        _ => mv.to(),
+    }
+}
+
+/// Where the rook goes from and to when `mv` castles.
+fn rook_squares(mv: Move) -> Option<(Position, Position)> {
+    match (mv.kind(), mv.from().to_xy()) {
+        (MoveKind::KingSideCastle, XY::OnBoard(_, y)) => {
+            Some((Position::new(7, y), Position::new(5, y)))
+        }
+        (MoveKind::QueenSideCastle, XY::OnBoard(_, y)) => {
+            Some((Position::new(0, y), Position::new(3, y)))
+        }
+        _ => None,
+    }
+}
+
+/// Whether `position` is a light square, as bishops never leave the color they start on.
+fn is_light_square(position: Position) -> bool {
+    match position.to_xy() {
+        XY::OnBoard(x, y) => (x + y) % 2 == 1,
+        XY::OffBoard => false,
+    }
 }
 
 impl Default for Board {
@@ -49,7 +543,10 @@ impl Default for Board {
 
         let pieces: Vec<Box<dyn Piece>> = pawns.chain(rest).collect();
 
-        Self { pieces }
+        let mut board = Board::new(pieces);
+        board.set_castling_rights(CastlingRights::all());
+
+        board
     }
 }
 
@@ -135,6 +632,14 @@ impl Display for Board {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
+    fn play_legal(board: &mut Board, from: (u8, u8), to: (u8, u8)) {
+        let mv = board
+            .legal_move(from.into(), to.into(), None)
+            .expect("move should be legal");
+        board.play(mv);
+    }
 
     #[test]
     fn display() {
@@ -156,4 +661,377 @@ mod tests {
 
         assert_eq!(board.to_string(), expected);
     }
+
+    #[test]
+    fn play() {
+        let mut board = Board::default();
+
+        play_legal(&mut board, (4, 1), (4, 3));
+        assert_eq!(board.side_to_move(), Color::Black);
+        assert_eq!(board.en_passant(), Some((4, 2).into()));
+        assert_eq!(board.halfmove_clock(), 0);
+        assert_eq!(board.fullmove_number(), 1);
+        assert!(board.piece_at((4, 1).into()).is_none());
+        assert_eq!(
+            board.piece_at((4, 3).into()).map(|piece| piece.kind()),
+            Some(PieceKind::Pawn)
+        );
+
+        play_legal(&mut board, (6, 7), (5, 5));
+        assert_eq!(board.side_to_move(), Color::White);
+        assert_eq!(board.en_passant(), None);
+        assert_eq!(board.halfmove_clock(), 1);
+        assert_eq!(board.fullmove_number(), 2);
+        assert_eq!(board.pieces().len(), 32);
+    }
+
+    #[test]
+    fn play_updates_castling_rights() {
+        let mut board = Board::default();
+
+        play_legal(&mut board, (7, 1), (7, 3));
+        play_legal(&mut board, (0, 6), (0, 4));
+        play_legal(&mut board, (7, 0), (7, 2));
+        assert_eq!(
+            board.castling_rights(),
+            CastlingRights {
+                white_king_side: false,
+                ..CastlingRights::all()
+            }
+        );
+
+        play_legal(&mut board, (4, 6), (4, 5));
+        play_legal(&mut board, (0, 1), (0, 2));
+        play_legal(&mut board, (4, 7), (4, 6));
+        assert_eq!(
+            board.castling_rights(),
+            CastlingRights {
+                white_queen_side: true,
+                ..CastlingRights::none()
+            }
+        );
+    }
+
+    #[test]
+    fn play_castles() {
+        let mut board = Board::new(vec![
+            Box::new(King::new(Color::White, (4, 0).into())),
+            Box::new(Rook::new(Color::White, (0, 0).into())),
+            Box::new(Rook::new(Color::White, (7, 0).into())),
+            Box::new(King::new(Color::Black, (4, 7).into())),
+            Box::new(Rook::new(Color::Black, (0, 7).into())),
+        ]);
+        board.set_castling_rights(CastlingRights::all());
+
+        assert!(board
+            .legal_move((4, 0).into(), (6, 0).into(), None)
+            .is_some());
+        play_legal(&mut board, (4, 0), (6, 0));
+        assert_eq!(
+            board.piece_at((5, 0).into()).map(|piece| piece.kind()),
+            Some(PieceKind::Rook)
+        );
+        assert!(board.piece_at((7, 0).into()).is_none());
+        assert_eq!(board.king_position(Color::White), Some((6, 0).into()));
+
+        assert!(board
+            .legal_move((4, 7).into(), (2, 7).into(), None)
+            .is_some());
+        play_legal(&mut board, (4, 7), (2, 7));
+        assert_eq!(
+            board.piece_at((3, 7).into()).map(|piece| piece.kind()),
+            Some(PieceKind::Rook)
+        );
+        assert!(board.piece_at((0, 7).into()).is_none());
+        assert_eq!(board.castling_rights(), CastlingRights::none());
+    }
+
+    #[test]
+    fn play_en_passant() {
+        let mut board = Board::default();
+
+        for (from, to) in [
+            ((4, 1), (4, 3)),
+            ((0, 6), (0, 5)),
+            ((4, 3), (4, 4)),
+            ((3, 6), (3, 4)),
+        ] {
+            play_legal(&mut board, from, to);
+        }
+        assert_eq!(board.en_passant(), Some((3, 5).into()));
+        assert!(board
+            .legal_move((4, 4).into(), (3, 5).into(), None)
+            .is_some());
+
+        play_legal(&mut board, (4, 4), (3, 5));
+        assert!(board.piece_at((3, 4).into()).is_none());
+        assert_eq!(board.pieces().len(), 31);
+        assert_eq!(board.en_passant(), None);
+    }
+
+    #[test]
+    fn en_passant_exposing_king() {
+        let mut board = Board::new(vec![
+            Box::new(King::new(Color::White, (0, 4).into())),
+            Box::new(Pawn::new(Color::White, (1, 4).into())),
+            Box::new(Pawn::new(Color::Black, (2, 6).into())),
+            Box::new(Rook::new(Color::Black, (7, 4).into())),
+            Box::new(King::new(Color::Black, (7, 7).into())),
+            Box::new(Knight::new(Color::White, (6, 0).into())),
+        ]);
+        play_legal(&mut board, (6, 0), (5, 2));
+        play_legal(&mut board, (2, 6), (2, 4));
+
+        // taking en passant would clear both pawns off the fifth rank
+        assert_eq!(board.en_passant(), Some((2, 5).into()));
+        assert!(board
+            .legal_move((1, 4).into(), (2, 5).into(), None)
+            .is_none());
+        assert!(board
+            .legal_move((1, 4).into(), (1, 5).into(), None)
+            .is_some());
+    }
+
+    #[test]
+    fn legal_moves_start() {
+        let board = Board::default();
+        let moves = board.legal_moves();
+
+        assert_eq!(moves.len(), 20);
+        assert_eq!(
+            moves
+                .iter()
+                .filter(|mv| mv.from() == (6, 0).into())
+                .map(|mv| mv.to())
+                .collect::<HashSet<_>>(),
+            HashSet::from([(5, 2).into(), (7, 2).into()])
+        );
+    }
+
+    #[test]
+    fn legal_moves_pinned() {
+        let mut board = Board::new(vec![
+            Box::new(King::new(Color::White, (4, 0).into())),
+            Box::new(Bishop::new(Color::White, (4, 1).into())),
+            Box::new(Rook::new(Color::Black, (4, 7).into())),
+            Box::new(King::new(Color::Black, (0, 7).into())),
+        ]);
+
+        let moves = board.legal_moves();
+        assert!(moves.iter().all(|mv| mv.from() != (4, 1).into()));
+        assert!(board
+            .legal_move((4, 1).into(), (3, 2).into(), None)
+            .is_none());
+
+        play_legal(&mut board, (4, 0), (3, 0));
+        play_legal(&mut board, (4, 7), (3, 7));
+        assert!(board
+            .legal_move((4, 1).into(), (3, 2).into(), None)
+            .is_some());
+    }
+
+    #[test]
+    fn legal_moves_king_into_check() {
+        let board = Board::new(vec![
+            Box::new(King::new(Color::White, (4, 0).into())),
+            Box::new(Rook::new(Color::Black, (3, 7).into())),
+            Box::new(Pawn::new(Color::Black, (6, 2).into())),
+            Box::new(Knight::new(Color::Black, (4, 2).into())),
+            Box::new(King::new(Color::Black, (0, 7).into())),
+        ]);
+
+        assert_eq!(
+            board.legal_moves(),
+            vec![Move::new((4, 0).into(), (4, 1).into())]
+        );
+
+        let board = Board::new(vec![
+            Box::new(King::new(Color::White, (4, 0).into())),
+            Box::new(Rook::new(Color::Black, (4, 1).into())),
+            Box::new(Rook::new(Color::Black, (4, 7).into())),
+        ]);
+
+        // the checking rook is defended, so the king can only step aside
+        assert_eq!(
+            board.legal_moves().into_iter().collect::<HashSet<_>>(),
+            HashSet::from([
+                Move::new((4, 0).into(), (3, 0).into()),
+                Move::new((4, 0).into(), (5, 0).into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn legal_moves_promotion() {
+        let board = Board::new(vec![
+            Box::new(Pawn::new(Color::White, (1, 6).into())),
+            Box::new(Rook::new(Color::Black, (2, 7).into())),
+            Box::new(Knight::new(Color::Black, (1, 7).into())),
+        ]);
+
+        let moves = board.legal_moves().into_iter().collect::<HashSet<_>>();
+        assert_eq!(
+            moves,
+            PieceKind::PROMOTIONS
+                .iter()
+                .map(|kind| Move::new((1, 6).into(), (2, 7).into())
+                    .with_captured(PieceKind::Rook)
+                    .with_promotion(*kind))
+                .collect()
+        );
+    }
+
+    #[test]
+    fn play_promotion() {
+        let mut board = Board::new(vec![
+            Box::new(King::new(Color::White, (7, 0).into())),
+            Box::new(Pawn::new(Color::White, (0, 6).into())),
+            Box::new(King::new(Color::Black, (7, 7).into())),
+        ]);
+
+        assert_eq!(board.legal_moves().len(), 3 + 4);
+        assert!(board
+            .legal_move((0, 6).into(), (0, 7).into(), None)
+            .is_none());
+        assert!(board
+            .legal_move((0, 6).into(), (0, 7).into(), Some(PieceKind::King))
+            .is_none());
+
+        let mv = Move::new((0, 6).into(), (0, 7).into()).with_promotion(PieceKind::Knight);
+        assert!(board.is_legal(mv));
+        board.play(mv);
+
+        let promoted = board.piece_at((0, 7).into()).unwrap();
+        assert_eq!(promoted.kind(), PieceKind::Knight);
+        assert_eq!(promoted.color(), Color::White);
+        assert!(board.piece_at((0, 6).into()).is_none());
+    }
+
+    #[test]
+    fn bitboards_follow_play() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k3/1P6/8/8/8/8/8/4K3 w q - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+
+            for mv in board.legal_moves() {
+                let mut after = board.clone();
+                after.play(mv);
+                assert_eq!(*after.bitboards(), Bitboards::new(after.pieces()));
+            }
+        }
+    }
+
+    #[test]
+    fn make_unmake() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 20",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k3/1P6/8/8/8/8/8/4K3 w q - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let pieces = |board: &Board| {
+                board
+                    .pieces()
+                    .iter()
+                    .map(|piece| (piece.kind(), piece.color(), piece.position()))
+                    .collect::<Vec<_>>()
+            };
+            let before = pieces(&board);
+
+            for mv in board.legal_moves() {
+                let undo = board.make_move(mv);
+                assert_eq!(undo.mv(), mv);
+                assert_ne!(board.to_fen(), fen);
+                assert_eq!(*board.bitboards(), Bitboards::new(board.pieces()));
+
+                board.unmake_move(undo);
+                assert_eq!(board.to_fen(), fen);
+                assert_eq!(pieces(&board), before);
+                assert_eq!(*board.bitboards(), Bitboards::new(board.pieces()));
+            }
+        }
+    }
+
+    #[test]
+    fn check_checkmate_stalemate() {
+        let mut board = Board::default();
+        assert!(!board.is_check());
+        assert!(!board.is_checkmate());
+        assert!(!board.is_stalemate());
+
+        for (from, to) in [((5, 1), (5, 2)), ((4, 6), (4, 4)), ((6, 1), (6, 3))] {
+            play_legal(&mut board, from, to);
+        }
+        play_legal(&mut board, (3, 7), (7, 3));
+        assert!(board.is_check());
+        assert!(board.is_checkmate());
+        assert!(!board.is_stalemate());
+
+        let board = Board::new(vec![
+            Box::new(King::new(Color::White, (0, 0).into())),
+            Box::new(Queen::new(Color::Black, (2, 1).into())),
+            Box::new(King::new(Color::Black, (7, 7).into())),
+        ]);
+        assert!(!board.is_check());
+        assert!(!board.is_checkmate());
+        assert!(board.is_stalemate());
+
+        let board = Board::new(vec![
+            Box::new(King::new(Color::White, (0, 0).into())),
+            Box::new(Queen::new(Color::Black, (1, 1).into())),
+            Box::new(King::new(Color::Black, (7, 7).into())),
+        ]);
+        assert!(board.is_check());
+        assert!(!board.is_checkmate());
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/5B2/8 w - - 0 1",
+            "8/8/4k3/8/2n5/3K4/8/8 w - - 0 1",
+            // bishops on dark squares only
+            "8/8/4k3/4b3/8/3K4/5B2/8 w - - 0 1",
+            "1b6/8/4k3/8/8/3K4/5B2/B7 w - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(board.has_insufficient_material(), "{}", fen);
+            assert!(board.is_dead_position(), "{}", fen);
+        }
+
+        for fen in [
+            "8/8/4k3/8/8/3K4/4P3/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/4R3/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/4NN2/8 w - - 0 1",
+            "8/8/4k3/8/2n5/3K4/5B2/8 w - - 0 1",
+            // bishops on squares of both colors
+            "8/8/4k3/3b4/8/3K4/5B2/8 w - - 0 1",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(!board.has_insufficient_material(), "{}", fen);
+        }
+        assert!(!Board::default().has_insufficient_material());
+    }
+
+    #[test]
+    fn dead_position() {
+        let locked = Board::from_fen("8/4k3/8/p1p1p1p1/P1P1P1P1/8/4K3/8 w - - 0 1").unwrap();
+        assert!(!locked.has_insufficient_material());
+        assert!(locked.is_dead_position());
+
+        // the same, but the pawn on h5 is free to move
+        let open = Board::from_fen("8/4k3/8/p1p1p2p/P1P1P3/8/4K3/8 w - - 0 1").unwrap();
+        assert!(!open.is_dead_position());
+
+        // bishops on squares of both colors can still help each other's king into a mate
+        let bishops = Board::from_fen("8/8/4k3/3b4/8/3K4/5B2/8 w - - 0 1").unwrap();
+        assert!(!bishops.is_dead_position());
+
+        assert!(!Board::default().is_dead_position());
+    }
 }
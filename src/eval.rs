@@ -0,0 +1,630 @@
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, Neg};
+
+use crate::bitboard::{
+    bishop_attacks, king_attacks, knight_attacks, queen_attacks, rook_attacks, Bitboard, FILE_A,
+};
+use crate::board::Board;
+use crate::piece::{Color, PieceKind};
+use crate::position::{Position, XY};
+
+/// The phase of a position with every piece still on the board. Knights and bishops
+/// count 1 each, rooks 2 and queens 4, so that 0 is left when only kings and pawns are.
+pub const OPENING_PHASE: i32 = 24;
+
+/// A score as a pair of middlegame and endgame values, blended by the game phase
+/// once everything has been added up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Tapered {
+    middlegame: i32,
+    endgame: i32,
+}
+
+const fn tapered(middlegame: i32, endgame: i32) -> Tapered {
+    Tapered {
+        middlegame,
+        endgame,
+    }
+}
+
+impl Tapered {
+    fn blend(self, phase: i32) -> i32 {
+        (self.middlegame * phase + self.endgame * (OPENING_PHASE - phase)) / OPENING_PHASE
+    }
+}
+
+impl Add for Tapered {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        tapered(
+            self.middlegame + other.middlegame,
+            self.endgame + other.endgame,
+        )
+    }
+}
+
+impl AddAssign for Tapered {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Mul<i32> for Tapered {
+    type Output = Self;
+
+    fn mul(self, factor: i32) -> Self {
+        tapered(self.middlegame * factor, self.endgame * factor)
+    }
+}
+
+impl Neg for Tapered {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self * -1
+    }
+}
+
+/// `evaluate` split up into its terms, each in centipawns from the side to move's
+/// point of view, so that a surprising score can be traced back to its cause.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Evaluation {
+    /// How much material is left, from `OPENING_PHASE` down to 0. The terms weigh
+    /// their middlegame and endgame values by it.
+    pub phase: i32,
+    pub material: i32,
+    pub piece_squares: i32,
+    pub mobility: i32,
+    pub pawn_structure: i32,
+    pub king_safety: i32,
+}
+
+impl Evaluation {
+    pub fn new(board: &Board) -> Self {
+        let phase = phase(board);
+        let perspective = match board.side_to_move() {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let term = |score: Tapered| score.blend(phase) * perspective;
+
+        Evaluation {
+            phase,
+            material: term(material(board)),
+            piece_squares: term(piece_squares(board)),
+            mobility: term(mobility(board)),
+            pawn_structure: term(pawn_structure(board)),
+            king_safety: term(king_safety(board)),
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.material + self.piece_squares + self.mobility + self.pawn_structure + self.king_safety
+    }
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<16}{:>6}", "phase", self.phase)?;
+        writeln!(f, "{:<16}{:>6}", "material", self.material)?;
+        writeln!(f, "{:<16}{:>6}", "piece squares", self.piece_squares)?;
+        writeln!(f, "{:<16}{:>6}", "mobility", self.mobility)?;
+        writeln!(f, "{:<16}{:>6}", "pawn structure", self.pawn_structure)?;
+        writeln!(f, "{:<16}{:>6}", "king safety", self.king_safety)?;
+        write!(f, "{:<16}{:>6}", "total", self.total())
+    }
+}
+
+/// How good `board` is for the side to move, in centipawns.
+pub fn evaluate(board: &Board) -> i32 {
+    Evaluation::new(board).total()
+}
+
+/// The middlegame value of a piece, for weighing up captures.
+pub fn piece_value(kind: PieceKind) -> i32 {
+    material_value(kind).middlegame
+}
+
+fn material_value(kind: PieceKind) -> Tapered {
+    match kind {
+        PieceKind::Pawn => tapered(100, 120),
+        PieceKind::Knight => tapered(320, 300),
+        PieceKind::Bishop => tapered(330, 320),
+        PieceKind::Rook => tapered(500, 550),
+        PieceKind::Queen => tapered(950, 1000),
+        PieceKind::King => tapered(0, 0),
+    }
+}
+
+fn phase(board: &Board) -> i32 {
+    let phase: i32 = board
+        .pieces()
+        .iter()
+        .map(|piece| match piece.kind() {
+            PieceKind::Knight | PieceKind::Bishop => 1,
+            PieceKind::Rook => 2,
+            PieceKind::Queen => 4,
+            PieceKind::Pawn | PieceKind::King => 0,
+        })
+        .sum();
+
+    // promotions can take it past the starting material
+    phase.min(OPENING_PHASE)
+}
+
+/// `score` as seen from White's side, which is how all terms are added up.
+fn for_white(color: Color, score: Tapered) -> Tapered {
+    match color {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+fn material(board: &Board) -> Tapered {
+    board
+        .pieces()
+        .iter()
+        .fold(Tapered::default(), |score, piece| {
+            score + for_white(piece.color(), material_value(piece.kind()))
+        })
+}
+
+// The tables below are laid out as White sees the board, with the eighth rank on top,
+// and are mirrored for Black.
+
+#[rustfmt::skip]
+const PAWN_MIDDLEGAME: [i32; 64] = [
+     0,  0,   0,   0,   0,   0,  0,  0,
+    50, 50,  50,  50,  50,  50, 50, 50,
+    10, 10,  20,  30,  30,  20, 10, 10,
+     5,  5,  10,  25,  25,  10,  5,  5,
+     0,  0,   0,  20,  20,   0,  0,  0,
+     5, -5, -10,   0,   0, -10, -5,  5,
+     5, 10,  10, -20, -20,  10, 10,  5,
+     0,  0,   0,   0,   0,   0,  0,  0,
+];
+
+#[rustfmt::skip]
+const PAWN_ENDGAME: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    80, 80, 80, 80, 80, 80, 80, 80,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    30, 30, 30, 30, 30, 30, 30, 30,
+    15, 15, 15, 15, 15, 15, 15, 15,
+     5,  5,  5,  5,  5,  5,  5,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+    -20, -10, -10, -5, -5, -10, -10, -20,
+    -10,   0,   0,  0,  0,   0,   0, -10,
+    -10,   0,   5,  5,  5,   5,   0, -10,
+     -5,   0,   5,  5,  5,   5,   0,  -5,
+      0,   0,   5,  5,  5,   5,   0,  -5,
+    -10,   5,   5,  5,  5,   5,   0, -10,
+    -10,   0,   5,  0,  0,   0,   0, -10,
+    -20, -10, -10, -5, -5, -10, -10, -20,
+];
+
+/// The king hides behind its pawns while there are pieces around to attack it...
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+/// ...and heads for the center once they are gone.
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+fn piece_square(kind: PieceKind, color: Color, position: Position) -> Tapered {
+    let XY::OnBoard(x, y) = position.to_xy() else {
+        return Tapered::default();
+    };
+
+    let index = match color {
+        Color::White => (7 - y) * 8 + x,
+        Color::Black => y * 8 + x,
+    } as usize;
+
+    match kind {
+        PieceKind::Pawn => tapered(PAWN_MIDDLEGAME[index], PAWN_ENDGAME[index]),
+        PieceKind::Knight => tapered(KNIGHT[index], KNIGHT[index]),
+        PieceKind::Bishop => tapered(BISHOP[index], BISHOP[index]),
+        PieceKind::Rook => tapered(ROOK[index], ROOK[index]),
+        PieceKind::Queen => tapered(QUEEN[index], QUEEN[index]),
+        PieceKind::King => tapered(KING_MIDDLEGAME[index], KING_ENDGAME[index]),
+    }
+}
+
+fn piece_squares(board: &Board) -> Tapered {
+    board
+        .pieces()
+        .iter()
+        .fold(Tapered::default(), |score, piece| {
+            score
+                + for_white(
+                    piece.color(),
+                    piece_square(piece.kind(), piece.color(), piece.position()),
+                )
+        })
+}
+
+/// What each square a piece could move to is worth. Pawns and kings are left to the
+/// pawn structure and king safety terms.
+fn mobility_value(kind: PieceKind) -> Tapered {
+    match kind {
+        PieceKind::Knight => tapered(4, 4),
+        PieceKind::Bishop => tapered(5, 5),
+        PieceKind::Rook => tapered(2, 4),
+        PieceKind::Queen => tapered(1, 2),
+        PieceKind::Pawn | PieceKind::King => tapered(0, 0),
+    }
+}
+
+/// Counts the squares each piece attacks that are not taken by its own side, which
+/// matches its moves while leaving out whether they are legal.
+fn mobility(board: &Board) -> Tapered {
+    let bitboards = board.bitboards();
+    let occupied = bitboards.occupied();
+    let mut score = Tapered::default();
+
+    for color in [Color::White, Color::Black] {
+        let own = bitboards.color(color);
+
+        for kind in [
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+        ] {
+            for position in bitboards.pieces(kind, color) {
+                let attacks = match kind {
+                    PieceKind::Knight => knight_attacks(position),
+                    PieceKind::Bishop => bishop_attacks(position, occupied),
+                    PieceKind::Rook => rook_attacks(position, occupied),
+                    _ => queen_attacks(position, occupied),
+                };
+                let squares = (attacks & !own).count() as i32;
+
+                score += for_white(color, mobility_value(kind) * squares);
+            }
+        }
+    }
+
+    score
+}
+
+const DOUBLED_PAWN: Tapered = tapered(-10, -20);
+const ISOLATED_PAWN: Tapered = tapered(-10, -15);
+/// By how far the pawn has come, from its own side's first rank.
+const PASSED_PAWN: [Tapered; 8] = [
+    tapered(0, 0),
+    tapered(5, 10),
+    tapered(10, 15),
+    tapered(15, 25),
+    tapered(25, 40),
+    tapered(40, 65),
+    tapered(60, 100),
+    tapered(0, 0),
+];
+
+fn file(x: u8) -> Bitboard {
+    Bitboard(FILE_A << x)
+}
+
+/// The files on either side of file `x`.
+fn adjacent_files(x: u8) -> Bitboard {
+    let left = if x > 0 { file(x - 1) } else { Bitboard::EMPTY };
+    let right = if x < 7 { file(x + 1) } else { Bitboard::EMPTY };
+
+    left | right
+}
+
+/// The ranks in front of rank `y`, as seen by `color`.
+fn ranks_ahead(color: Color, y: u8) -> Bitboard {
+    match color {
+        Color::White if y < 7 => Bitboard(u64::MAX << (8 * (y + 1))),
+        Color::Black => Bitboard((1 << (8 * y)) - 1),
+        Color::White => Bitboard::EMPTY,
+    }
+}
+
+fn pawn_structure(board: &Board) -> Tapered {
+    [Color::White, Color::Black]
+        .into_iter()
+        .fold(Tapered::default(), |score, color| {
+            score + for_white(color, pawn_structure_for(board, color))
+        })
+}
+
+fn pawn_structure_for(board: &Board, color: Color) -> Tapered {
+    let own = board.bitboards().pieces(PieceKind::Pawn, color);
+    let enemy = board.bitboards().pieces(PieceKind::Pawn, color.opposite());
+    let mut score = Tapered::default();
+
+    for x in 0..8 {
+        let count = (own & file(x)).count() as i32;
+        if count > 1 {
+            score += DOUBLED_PAWN * (count - 1);
+        }
+    }
+
+    for pawn in own {
+        let XY::OnBoard(x, y) = pawn.to_xy() else {
+            continue;
+        };
+
+        if (own & adjacent_files(x)).is_empty() {
+            score += ISOLATED_PAWN;
+        }
+
+        let blockers = ranks_ahead(color, y) & (file(x) | adjacent_files(x));
+        if (enemy & blockers).is_empty() {
+            let advanced = match color {
+                Color::White => y,
+                Color::Black => 7 - y,
+            };
+            score += PASSED_PAWN[advanced as usize];
+        }
+    }
+
+    score
+}
+
+/// For each pawn right in front of the king.
+const PAWN_SHIELD: Tapered = tapered(12, 0);
+/// For each file next to the king without a pawn of its own.
+const OPEN_FILE: Tapered = tapered(-15, 0);
+/// For each attack on the squares around the king.
+const ATTACKED_SQUARE: Tapered = tapered(-8, 0);
+
+fn king_safety(board: &Board) -> Tapered {
+    [Color::White, Color::Black]
+        .into_iter()
+        .fold(Tapered::default(), |score, color| {
+            score + for_white(color, king_safety_for(board, color))
+        })
+}
+
+fn king_safety_for(board: &Board, color: Color) -> Tapered {
+    let bitboards = board.bitboards();
+    let Some(king) = bitboards.king_position(color) else {
+        return Tapered::default();
+    };
+    let XY::OnBoard(x, y) = king.to_xy() else {
+        return Tapered::default();
+    };
+
+    let pawns = bitboards.pieces(PieceKind::Pawn, color);
+    let files = file(x) | adjacent_files(x);
+
+    // the two ranks in front of the king
+    let in_front = ranks_ahead(color, y)
+        & match color {
+            Color::White => !ranks_ahead(color, (y + 2).min(7)),
+            Color::Black => !ranks_ahead(color, y.saturating_sub(2)),
+        };
+    let shield = (pawns & files & in_front).count() as i32;
+
+    let open_files = (x.saturating_sub(1)..=(x + 1).min(7))
+        .filter(|x| (pawns & file(*x)).is_empty())
+        .count() as i32;
+
+    let attacks: i32 = (king_attacks(king) | Bitboard::from_position(king))
+        .map(|square| bitboards.attackers(square, color.opposite()).count() as i32)
+        .sum();
+
+    PAWN_SHIELD * shield + OPEN_FILE * open_files + ATTACKED_SQUARE * attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::STARTING_FEN;
+
+    /// The same position with the colors swapped and the board turned around.
+    fn mirror(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let swap_case = |text: &str| -> String {
+            text.chars()
+                .map(|c| {
+                    if c.is_ascii_uppercase() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        c.to_ascii_uppercase()
+                    }
+                })
+                .collect()
+        };
+
+        let placement: Vec<String> = fields[0].split('/').rev().map(swap_case).collect();
+        let side = if fields[1] == "w" { "b" } else { "w" };
+        let en_passant = match fields[3] {
+            "-" => "-".to_owned(),
+            square => square
+                .chars()
+                .map(|c| match c {
+                    '3' => '6',
+                    '6' => '3',
+                    c => c,
+                })
+                .collect(),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement.join("/"),
+            side,
+            swap_case(fields[2]),
+            en_passant,
+            fields[4],
+            fields[5]
+        )
+    }
+
+    fn evaluation(fen: &str) -> Evaluation {
+        Evaluation::new(&Board::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn starting_position() {
+        let evaluation = evaluation(STARTING_FEN);
+
+        assert_eq!(evaluation.phase, OPENING_PHASE);
+        assert_eq!(
+            evaluation,
+            Evaluation {
+                phase: OPENING_PHASE,
+                ..Evaluation::default()
+            }
+        );
+        assert_eq!(evaluate(&Board::default()), 0);
+    }
+
+    #[test]
+    fn symmetry() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/3P4/8/8/4K3 b - - 0 1",
+        ] {
+            assert_eq!(evaluation(fen), evaluation(&mirror(fen)), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn side_to_move() {
+        let white = evaluation("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black = evaluation("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+
+        assert!(white.material > 0);
+        assert_eq!(white.material, -black.material);
+        assert_eq!(white.total(), -black.total());
+    }
+
+    #[test]
+    fn phase() {
+        assert_eq!(
+            evaluation("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1").phase,
+            0
+        );
+        assert_eq!(evaluation("3qk3/8/8/8/8/8/8/2R1K3 w - - 0 1").phase, 6);
+
+        // a pawn is worth more in the endgame
+        assert_eq!(evaluation("4k3/8/8/8/8/8/P7/4K3 w - - 0 1").material, 120);
+        assert_eq!(
+            evaluation("r2qk3/8/8/8/8/8/P7/R2QK3 w - - 0 1").material,
+            (100 * 12 + 120 * 12) / 24
+        );
+
+        // and the king belongs in the center
+        let center = evaluation("8/8/8/3k4/8/8/8/K7 w - - 0 1");
+        assert!(center.piece_squares < 0);
+    }
+
+    #[test]
+    fn mobility() {
+        let cornered = evaluation("4k3/8/8/8/8/8/8/N3K3 w - - 0 1");
+        let central = evaluation("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1");
+
+        assert_eq!(cornered.mobility, 2 * 4);
+        assert_eq!(central.mobility, 8 * 4);
+    }
+
+    #[test]
+    fn pawn_structure() {
+        let healthy = evaluation("4k3/8/8/8/8/8/PPP5/4K3 w - - 0 1");
+        let doubled = evaluation("4k3/8/8/8/8/P7/P1P5/4K3 w - - 0 1");
+        let isolated = evaluation("4k3/8/8/8/8/8/P1P1P3/4K3 w - - 0 1");
+
+        assert!(doubled.pawn_structure < healthy.pawn_structure);
+        assert!(isolated.pawn_structure < healthy.pawn_structure);
+
+        // the pawn on d5 has no black pawn in its way, the one on b4 does
+        let passed = evaluation("4k3/8/p7/3P4/1P6/8/8/4K3 w - - 0 1");
+        let blocked = evaluation("4k3/8/p1p5/3P4/1P6/8/8/4K3 w - - 0 1");
+        assert!(passed.pawn_structure > blocked.pawn_structure);
+
+        let far = evaluation("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let near = evaluation("4k3/8/8/8/8/1P6/8/4K3 w - - 0 1");
+        assert!(far.pawn_structure > near.pawn_structure);
+    }
+
+    #[test]
+    fn king_safety() {
+        let sheltered = evaluation("r2qk3/8/8/8/8/8/5PPP/3Q2K1 w - - 0 1");
+        let exposed = evaluation("r2qk3/8/8/8/5PPP/8/8/3Q2K1 w - - 0 1");
+
+        assert!(sheltered.king_safety > exposed.king_safety);
+
+        // the rook on the open file looks right at the king
+        let attacked = evaluation("3qk1r1/8/8/8/8/8/5P1P/3Q2K1 w - - 0 1");
+        let defended = evaluation("3qk1r1/8/8/8/8/8/5PPP/3Q2K1 w - - 0 1");
+        assert!(attacked.king_safety < defended.king_safety);
+    }
+
+    #[test]
+    fn display() {
+        let text = evaluation(STARTING_FEN).to_string();
+
+        assert!(text.starts_with("phase               24\n"));
+        assert!(text.ends_with("total                0"));
+        assert_eq!(text.lines().count(), 7);
+    }
+}
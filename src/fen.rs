@@ -0,0 +1,503 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::board::{Board, CastlingRights};
+use crate::piece::{new_piece, Color, Piece, PieceKind};
+use crate::position::{Position, XY};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// A FEN has six space-separated fields; the two move counters may be left off.
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    /// `rank` is the chess rank, 1 through 8, and `length` the number of squares it described.
+    BadRankLength {
+        rank: u8,
+        length: usize,
+    },
+    /// A run of empty squares on `rank` was written as more than one digit, like "44".
+    ConsecutiveDigits {
+        rank: u8,
+    },
+    UnknownPiece(char),
+    /// Pawns promote on reaching the last rank, so none can stand on rank 1 or 8.
+    PawnOnBackRank {
+        rank: u8,
+    },
+    /// Each side has to have exactly one king.
+    WrongKingCount {
+        color: Color,
+        count: usize,
+    },
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => {
+                write!(f, "expected 4 or 6 fields, found {}", count)
+            }
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::BadRankLength { rank, length } => {
+                write!(f, "rank {} describes {} squares instead of 8", rank, length)
+            }
+            FenError::ConsecutiveDigits { rank } => {
+                write!(f, "rank {} has two digits in a row", rank)
+            }
+            FenError::UnknownPiece(letter) => write!(f, "unknown piece letter '{}'", letter),
+            FenError::PawnOnBackRank { rank } => write!(f, "rank {} has a pawn on it", rank),
+            FenError::WrongKingCount { color, count } => {
+                let side = match color {
+                    Color::White => "white",
+                    Color::Black => "black",
+                };
+                write!(f, "{} has {} kings instead of 1", side, count)
+            }
+            FenError::InvalidSideToMove(field) => {
+                write!(f, "invalid side to move \"{}\"", field)
+            }
+            FenError::InvalidCastling(field) => {
+                write!(f, "invalid castling availability \"{}\"", field)
+            }
+            FenError::InvalidEnPassant(field) => {
+                write!(f, "impossible en passant square \"{}\"", field)
+            }
+            FenError::InvalidHalfmoveClock(field) => {
+                write!(f, "invalid halfmove clock \"{}\"", field)
+            }
+            FenError::InvalidFullmoveNumber(field) => {
+                write!(f, "invalid fullmove number \"{}\"", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Board {
+    /// Parses a position in Forsyth-Edwards Notation.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+
+        if fields.len() != 6 && fields.len() != 4 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut board = Board::new(parse_placement(fields[0])?);
+
+        let side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_owned())),
+        };
+        board.set_side_to_move(side_to_move);
+
+        let castling_rights = parse_castling(fields[2], &board)
+            .ok_or_else(|| FenError::InvalidCastling(fields[2].to_owned()))?;
+        board.set_castling_rights(castling_rights);
+
+        let en_passant = parse_en_passant(fields[3], &board)
+            .ok_or_else(|| FenError::InvalidEnPassant(fields[3].to_owned()))?;
+        board.set_en_passant(en_passant);
+
+        if let Some(field) = fields.get(4) {
+            let halfmove_clock = field
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(field.to_string()))?;
+            board.set_halfmove_clock(halfmove_clock);
+        }
+
+        if let Some(field) = fields.get(5) {
+            let fullmove_number = field
+                .parse()
+                .ok()
+                .filter(|number| *number > 0)
+                .ok_or_else(|| FenError::InvalidFullmoveNumber(field.to_string()))?;
+            board.set_fullmove_number(fullmove_number);
+        }
+
+        Ok(board)
+    }
+
+    /// Writes the position out in Forsyth-Edwards Notation, with castling rights in `KQkq` order.
+    pub fn to_fen(&self) -> String {
+        let placement = (0..8u8)
+            .rev()
+            .map(|y| {
+                let mut rank = String::new();
+                let mut empty = 0;
+
+                for x in 0..8u8 {
+                    match self.piece_at(Position::new(x, y)) {
+                        Some(piece) => {
+                            if empty > 0 {
+                                rank.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+
+                            rank.push(match piece.color() {
+                                Color::Black => piece.kind().letter().to_ascii_lowercase(),
+                                Color::White => piece.kind().letter(),
+                            });
+                        }
+                        None => empty += 1,
+                    }
+                }
+
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                }
+
+                rank
+            })
+            .collect::<Vec<String>>()
+            .join("/");
+
+        let side_to_move = match self.side_to_move() {
+            Color::Black => "b",
+            Color::White => "w",
+        };
+
+        let castling_rights = self.castling_rights();
+        let mut castling: String = [
+            (castling_rights.white_king_side, 'K'),
+            (castling_rights.white_queen_side, 'Q'),
+            (castling_rights.black_king_side, 'k'),
+            (castling_rights.black_queen_side, 'q'),
+        ]
+        .into_iter()
+        .filter(|(right, _)| *right)
+        .map(|(_, letter)| letter)
+        .collect();
+
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant()
+            .map_or_else(|| "-".to_owned(), |position| position.to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            side_to_move,
+            castling,
+            en_passant,
+            self.halfmove_clock(),
+            self.fullmove_number()
+        )
+    }
+}
+
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Board::from_fen(s)
+    }
+}
+
+fn parse_placement(field: &str) -> Result<Vec<Box<dyn Piece>>, FenError> {
+    let ranks: Vec<&str> = field.split('/').collect();
+
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    let mut pieces = vec![];
+
+    for (rank, y) in ranks.into_iter().zip((0..8u8).rev()) {
+        let mut length = 0;
+        let mut after_digit = false;
+
+        for letter in rank.chars() {
+            if let Some(empty) = letter.to_digit(10).filter(|digit| (1..=8).contains(digit)) {
+                if after_digit {
+                    return Err(FenError::ConsecutiveDigits { rank: y + 1 });
+                }
+                after_digit = true;
+                length += empty as usize;
+                continue;
+            }
+            after_digit = false;
+
+            let kind = PieceKind::from_letter(letter).ok_or(FenError::UnknownPiece(letter))?;
+            let color = if letter.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            if kind == PieceKind::Pawn && (y == 0 || y == 7) {
+                return Err(FenError::PawnOnBackRank { rank: y + 1 });
+            }
+
+            if length < 8 {
+                pieces.push(new_piece(kind, color, Position::new(length as u8, y)));
+            }
+            length += 1;
+        }
+
+        if length != 8 {
+            return Err(FenError::BadRankLength {
+                rank: y + 1,
+                length,
+            });
+        }
+    }
+
+    for color in [Color::White, Color::Black] {
+        let count = pieces
+            .iter()
+            .filter(|piece| piece.kind() == PieceKind::King && piece.color() == color)
+            .count();
+
+        if count != 1 {
+            return Err(FenError::WrongKingCount { color, count });
+        }
+    }
+
+    Ok(pieces)
+}
+
+/// Every right has to be backed by a king and rook still on their starting squares.
+fn parse_castling(field: &str, board: &Board) -> Option<CastlingRights> {
+    let mut castling_rights = CastlingRights::none();
+
+    if field == "-" {
+        return Some(castling_rights);
+    }
+
+    let is = |x: u8, y: u8, kind: PieceKind, color: Color| {
+        board
+            .piece_at(Position::new(x, y))
+            .is_some_and(|piece| piece.kind() == kind && piece.color() == color)
+    };
+
+    for letter in field.chars() {
+        let (right, color, rook_x) = match letter {
+            'K' => (&mut castling_rights.white_king_side, Color::White, 7),
+            'Q' => (&mut castling_rights.white_queen_side, Color::White, 0),
+            'k' => (&mut castling_rights.black_king_side, Color::Black, 7),
+            'q' => (&mut castling_rights.black_queen_side, Color::Black, 0),
+            _ => return None,
+        };
+
+        let y = match color {
+            Color::Black => 7,
+            Color::White => 0,
+        };
+
+        if *right || !is(4, y, PieceKind::King, color) || !is(rook_x, y, PieceKind::Rook, color) {
+            return None;
+        }
+
+        *right = true;
+    }
+
+    Some(castling_rights)
+}
+
+/// The square has to be one a pawn of the side that just moved skipped over:
+/// empty, with that pawn right in front of it and its starting square empty.
+fn parse_en_passant(field: &str, board: &Board) -> Option<Option<Position>> {
+    if field == "-" {
+        return Some(None);
+    }
+
+    let position: Position = field.parse().ok()?;
+    let mover = board.side_to_move().opposite();
+
+    let (pawn, start) = match (mover, position.to_xy()) {
+        (Color::White, XY::OnBoard(_, 2)) => (position.up(), position.down()),
+        (Color::Black, XY::OnBoard(_, 5)) => (position.down(), position.up()),
+        _ => return None,
+    };
+
+    let pushed_pawn = board
+        .piece_at(pawn)
+        .is_some_and(|piece| piece.kind() == PieceKind::Pawn && piece.color() == mover);
+
+    if pushed_pawn && board.piece_at(position).is_none() && board.piece_at(start).is_none() {
+        Some(Some(position))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starting_position() {
+        let board = Board::from_fen(STARTING_FEN).unwrap();
+
+        assert_eq!(board.to_string(), Board::default().to_string());
+        assert_eq!(board.side_to_move(), Color::White);
+        assert_eq!(board.castling_rights(), CastlingRights::all());
+        assert_eq!(board.en_passant(), None);
+        assert_eq!(board.halfmove_clock(), 0);
+        assert_eq!(board.fullmove_number(), 1);
+        assert_eq!(board.legal_moves().len(), 20);
+    }
+
+    #[test]
+    fn all_fields() {
+        let board: Board = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3"
+            .parse()
+            .unwrap();
+
+        assert_eq!(board.pieces().len(), 32);
+        assert_eq!(
+            board.piece_at((4, 4).into()).map(|piece| piece.kind()),
+            Some(PieceKind::Pawn)
+        );
+        assert_eq!(
+            board.castling_rights(),
+            CastlingRights {
+                white_king_side: true,
+                black_queen_side: true,
+                ..CastlingRights::none()
+            }
+        );
+        assert_eq!(board.en_passant(), Some((5, 5).into()));
+        assert_eq!(board.fullmove_number(), 3);
+        assert!(board
+            .legal_move((4, 4).into(), (5, 5).into(), None)
+            .is_some());
+
+        let board = Board::from_fen("8/8/8/8/8/8/8/k6K b - - 42 80").unwrap();
+        assert_eq!(board.side_to_move(), Color::Black);
+        assert_eq!(board.halfmove_clock(), 42);
+        assert_eq!(board.fullmove_number(), 80);
+
+        let board = Board::from_fen("8/8/8/8/8/8/8/k6K b - -").unwrap();
+        assert_eq!(board.halfmove_clock(), 0);
+        assert_eq!(board.fullmove_number(), 1);
+    }
+
+    #[test]
+    fn to_fen() {
+        assert_eq!(Board::default().to_fen(), STARTING_FEN);
+
+        for fen in [
+            STARTING_FEN,
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "8/8/8/8/8/8/8/k6K b - - 42 80",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+
+        let mut board = Board::default();
+        let mv = board
+            .legal_move((4, 1).into(), (4, 3).into(), None)
+            .unwrap();
+        board.play(mv);
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("8/8/8/8/8/8/8/k6K w - - 0", FenError::WrongFieldCount(5)),
+            ("8/8/8/8/8/8/k6K w - - 0 1", FenError::WrongRankCount(7)),
+            (
+                "8/8/8/8/8/8/8/k5K w - - 0 1",
+                FenError::BadRankLength { rank: 1, length: 7 },
+            ),
+            (
+                "8/8/8/8/8/ppppppppp/8/k6K w - - 0 1",
+                FenError::BadRankLength { rank: 3, length: 9 },
+            ),
+            (
+                "8/8/8/8/44/8/8/k6K w - - 0 1",
+                FenError::ConsecutiveDigits { rank: 4 },
+            ),
+            (
+                "8/8/8/8/8/8/8/k51K w - - 0 1",
+                FenError::ConsecutiveDigits { rank: 1 },
+            ),
+            ("8/8/8/8/8/8/8/k6X w - - 0 1", FenError::UnknownPiece('X')),
+            (
+                "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::PawnOnBackRank { rank: 8 },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/3pK3 b - - 0 1",
+                FenError::PawnOnBackRank { rank: 1 },
+            ),
+            (
+                "8/8/8/8/8/8/8/8 w - - 0 1",
+                FenError::WrongKingCount {
+                    color: Color::White,
+                    count: 0,
+                },
+            ),
+            (
+                "k6k/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::WrongKingCount {
+                    color: Color::Black,
+                    count: 2,
+                },
+            ),
+            (
+                "8/8/8/8/8/8/8/k6K x - - 0 1",
+                FenError::InvalidSideToMove("x".into()),
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkqK - 0 1",
+                FenError::InvalidCastling("KQkqK".into()),
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K1R1 w KQkq - 0 1",
+                FenError::InvalidCastling("KQkq".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/k6K w X - 0 1",
+                FenError::InvalidCastling("X".into()),
+            ),
+            (
+                "8/8/8/4P3/8/8/8/k6K w - e3 0 1",
+                FenError::InvalidEnPassant("e3".into()),
+            ),
+            (
+                "8/8/8/8/4P3/8/8/k6K b - e6 0 1",
+                FenError::InvalidEnPassant("e6".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/k6K b - e3 0 1",
+                FenError::InvalidEnPassant("e3".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/k6K b - z9 0 1",
+                FenError::InvalidEnPassant("z9".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/k6K b - - x 1",
+                FenError::InvalidHalfmoveClock("x".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/k6K b - - 0 0",
+                FenError::InvalidFullmoveNumber("0".into()),
+            ),
+        ];
+
+        for (fen, error) in cases {
+            assert_eq!(Board::from_fen(fen).err(), Some(error), "{}", fen);
+        }
+    }
+}
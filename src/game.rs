@@ -0,0 +1,568 @@
+use std::fmt::Display;
+
+use crate::board::{Board, CastlingRights};
+use crate::moves::Move;
+use crate::piece::Color;
+use crate::position::Position;
+
+pub struct Game {
+    starting_board: Board,
+    board: Board,
+    moves: Vec<Move>,
+    /// The hash of every position so far, starting with `starting_board`.
+    history: Vec<u64>,
+    /// Whether to look for dead positions beyond those with insufficient material.
+    detect_dead_positions: bool,
+    outcome: Option<GameOutcome>,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum GameOutcome {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason),
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum DrawReason {
+    Agreement,
+    Stalemate,
+    /// Claimed after the same position came up for the third time.
+    ThreefoldRepetition,
+    /// The same position came up for the fifth time, which ends the game on its own.
+    FivefoldRepetition,
+    /// Claimed after fifty moves by each side without a capture or a pawn move.
+    FiftyMoveRule,
+    /// Seventy-five moves by each side without a capture or a pawn move, which ends the
+    /// game on its own.
+    SeventyFiveMoveRule,
+    /// Neither side has the material left to give checkmate.
+    InsufficientMaterial,
+    /// No sequence of moves can lead to checkmate, though both sides still have enough
+    /// material. Only detected after `Game::set_detect_dead_positions`.
+    DeadPosition,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    GameOver(GameOutcome),
+    NoPieceAt(Position),
+    NotYourPiece(Position),
+    IllegalMove(Move),
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::GameOver(outcome) => write!(f, "the game is already over: {:?}", outcome),
+            MoveError::NoPieceAt(position) => write!(f, "there is no piece on {}", position),
+            MoveError::NotYourPiece(position) => {
+                write!(f, "the piece on {} belongs to the other side", position)
+            }
+            MoveError::IllegalMove(mv) => {
+                write!(f, "illegal move from {} to {}", mv.from(), mv.to())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::from_board(Board::default())
+    }
+
+    pub fn from_board(board: Board) -> Self {
+        let mut game = Self {
+            starting_board: board.clone(),
+            history: vec![board.hash()],
+            board,
+            moves: vec![],
+            detect_dead_positions: false,
+            outcome: None,
+        };
+        game.update_outcome();
+
+        game
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The position the game started from, before any of `moves` were played.
+    pub fn starting_board(&self) -> &Board {
+        &self.starting_board
+    }
+
+    /// The moves played so far, in order.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.board.side_to_move()
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.board.castling_rights()
+    }
+
+    pub fn en_passant(&self) -> Option<Position> {
+        self.board.en_passant()
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.board.halfmove_clock()
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.board.fullmove_number()
+    }
+
+    /// Also ends the game when no sequence of moves can lead to checkmate despite enough
+    /// material, such as when only the kings can move behind locked pawns. This search
+    /// is much slower than the material check, so it is off by default.
+    pub fn set_detect_dead_positions(&mut self, detect_dead_positions: bool) {
+        self.detect_dead_positions = detect_dead_positions;
+
+        if self.outcome.is_none() {
+            self.update_outcome();
+        }
+    }
+
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        self.outcome
+    }
+
+    /// Plays the legal move matching `mv`'s squares and promotion. The rest of `mv`
+    /// (its kind and captured piece) is worked out from the board, so `Move::new` is enough.
+    pub fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        if let Some(outcome) = self.outcome {
+            return Err(MoveError::GameOver(outcome));
+        }
+
+        let piece = self
+            .board
+            .piece_at(mv.from())
+            .ok_or(MoveError::NoPieceAt(mv.from()))?;
+
+        if piece.color() != self.board.side_to_move() {
+            return Err(MoveError::NotYourPiece(mv.from()));
+        }
+
+        let mv = self
+            .board
+            .legal_move(mv.from(), mv.to(), mv.promotion())
+            .ok_or(MoveError::IllegalMove(mv))?;
+
+        self.board.play(mv);
+        self.moves.push(mv);
+        self.history.push(self.board.hash());
+        self.update_outcome();
+
+        Ok(())
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.board.is_check()
+    }
+
+    /// How many times the current position has come up in this game, counting this time.
+    /// Positions only count as the same if the same side is to move with the same castling
+    /// rights and the same chance to take en passant.
+    pub fn repetitions(&self) -> usize {
+        let current = self.board.hash();
+
+        // a capture or pawn move can never be undone, so nothing before it can repeat
+        self.history
+            .iter()
+            .rev()
+            .take(self.board.halfmove_clock() as usize + 1)
+            .filter(|hash| **hash == current)
+            .count()
+    }
+
+    /// Whether the side to move may claim a draw because the position has come up
+    /// three times.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+
+    /// Whether the side to move may claim a draw because both sides have made fifty
+    /// moves without a capture or a pawn move.
+    pub fn is_fifty_move_rule(&self) -> bool {
+        self.board.halfmove_clock() >= 100
+    }
+
+    /// Ends the game in a draw if the side to move is entitled to claim one.
+    /// Returns whether the claim was accepted.
+    pub fn claim_draw(&mut self) -> bool {
+        if self.outcome.is_some() {
+            return false;
+        }
+
+        if self.is_threefold_repetition() {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::ThreefoldRepetition));
+            return true;
+        }
+
+        if self.is_fifty_move_rule() {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::FiftyMoveRule));
+            return true;
+        }
+
+        false
+    }
+
+    /// Ends the game if the side to move has been checkmated or stalemated, checkmate has
+    /// become impossible, or the position has repeated or gone without progress for long
+    /// enough to draw without a claim. Checkmate on the move that reaches the limit still
+    /// counts.
+    fn update_outcome(&mut self) {
+        if self.board.is_checkmate() {
+            self.outcome = Some(match self.board.side_to_move() {
+                Color::Black => GameOutcome::WhiteWins,
+                Color::White => GameOutcome::BlackWins,
+            });
+        } else if self.board.is_stalemate() {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::Stalemate));
+        } else if self.board.has_insufficient_material() {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::InsufficientMaterial));
+        } else if self.detect_dead_positions && self.board.is_dead_position() {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::DeadPosition));
+        } else if self.repetitions() >= 5 {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::FivefoldRepetition));
+        } else if self.board.halfmove_clock() >= 150 {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::SeventyFiveMoveRule));
+        }
+    }
+
+    pub fn resign(&mut self, color: Color) {
+        if self.outcome.is_none() {
+            self.outcome = Some(match color {
+                Color::Black => GameOutcome::WhiteWins,
+                Color::White => GameOutcome::BlackWins,
+            });
+        }
+    }
+
+    pub fn agree_draw(&mut self) {
+        if self.outcome.is_none() {
+            self.outcome = Some(GameOutcome::Draw(DrawReason::Agreement));
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::MoveKind;
+    use crate::piece::{King, Queen};
+
+    #[test]
+    fn make_move() {
+        let mut game = Game::new();
+
+        assert_eq!(
+            game.make_move(Move::new((4, 1).into(), (4, 3).into())),
+            Ok(())
+        );
+        assert_eq!(game.side_to_move(), Color::Black);
+        assert_eq!(game.en_passant(), Some((4, 2).into()));
+
+        assert_eq!(
+            game.make_move(Move::new((6, 7).into(), (5, 5).into())),
+            Ok(())
+        );
+        assert_eq!(game.side_to_move(), Color::White);
+        assert_eq!(game.halfmove_clock(), 1);
+        assert_eq!(game.fullmove_number(), 2);
+
+        assert_eq!(game.moves().len(), 2);
+        assert_eq!(game.moves()[0].kind(), MoveKind::DoublePawnPush);
+        assert_eq!(game.starting_board().to_fen(), Board::default().to_fen());
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let mut game = Game::new();
+
+        assert_eq!(
+            game.make_move(Move::new((4, 3).into(), (4, 4).into())),
+            Err(MoveError::NoPieceAt((4, 3).into()))
+        );
+        assert_eq!(
+            game.make_move(Move::new((4, 6).into(), (4, 4).into())),
+            Err(MoveError::NotYourPiece((4, 6).into()))
+        );
+        assert_eq!(
+            game.make_move(Move::new((4, 1).into(), (4, 4).into())),
+            Err(MoveError::IllegalMove(Move::new(
+                (4, 1).into(),
+                (4, 4).into()
+            )))
+        );
+        assert_eq!(game.side_to_move(), Color::White);
+    }
+
+    #[test]
+    fn rejects_moves_into_check() {
+        let mut game = Game::new();
+
+        for (from, to) in [((4, 1), (4, 3)), ((3, 6), (3, 5)), ((5, 0), (1, 4))] {
+            assert_eq!(game.make_move(Move::new(from.into(), to.into())), Ok(()));
+        }
+
+        // the bishop on b5 gives check, so black has to answer it
+        assert!(game.is_check());
+        assert_eq!(
+            game.make_move(Move::new((0, 6).into(), (0, 5).into())),
+            Err(MoveError::IllegalMove(Move::new(
+                (0, 6).into(),
+                (0, 5).into()
+            )))
+        );
+        assert_eq!(
+            game.make_move(Move::new((2, 6).into(), (2, 5).into())),
+            Ok(())
+        );
+        assert_eq!(game.outcome(), None);
+    }
+
+    #[test]
+    fn checkmate() {
+        let mut game = Game::new();
+
+        for (from, to) in [
+            ((5, 1), (5, 2)),
+            ((4, 6), (4, 4)),
+            ((6, 1), (6, 3)),
+            ((3, 7), (7, 3)),
+        ] {
+            assert_eq!(game.make_move(Move::new(from.into(), to.into())), Ok(()));
+        }
+
+        assert!(game.is_check());
+        assert_eq!(game.outcome(), Some(GameOutcome::BlackWins));
+        assert_eq!(
+            game.make_move(Move::new((0, 1).into(), (0, 2).into())),
+            Err(MoveError::GameOver(GameOutcome::BlackWins))
+        );
+    }
+
+    #[test]
+    fn stalemate() {
+        let board = Board::new(vec![
+            Box::new(King::new(Color::White, (5, 6).into())),
+            Box::new(Queen::new(Color::White, (6, 4).into())),
+            Box::new(King::new(Color::Black, (7, 7).into())),
+        ]);
+        let mut game = Game::from_board(board);
+        assert_eq!(game.outcome(), None);
+
+        assert_eq!(
+            game.make_move(Move::new((6, 4).into(), (6, 5).into())),
+            Ok(())
+        );
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::Stalemate))
+        );
+    }
+
+    #[test]
+    fn outcome() {
+        let mut game = Game::new();
+        assert_eq!(game.outcome(), None);
+
+        game.resign(Color::White);
+        assert_eq!(game.outcome(), Some(GameOutcome::BlackWins));
+
+        game.agree_draw();
+        assert_eq!(game.outcome(), Some(GameOutcome::BlackWins));
+
+        assert_eq!(
+            game.make_move(Move::new((4, 1).into(), (4, 3).into())),
+            Err(MoveError::GameOver(GameOutcome::BlackWins))
+        );
+    }
+
+    fn play_san(game: &mut Game, moves: &[&str]) {
+        for san in moves {
+            let mv = game.board().parse_san(san).unwrap();
+            assert_eq!(game.make_move(mv), Ok(()), "{}", san);
+        }
+    }
+
+    #[test]
+    fn repetition() {
+        let mut game = Game::new();
+        assert_eq!(game.repetitions(), 1);
+
+        play_san(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(game.repetitions(), 2);
+        assert!(!game.is_threefold_repetition());
+        assert!(!game.claim_draw());
+
+        play_san(&mut game, &["Nf3", "Nf6", "Ng1"]);
+        assert_eq!(game.repetitions(), 2);
+
+        play_san(&mut game, &["Ng8"]);
+        assert!(game.is_threefold_repetition());
+        assert_eq!(game.outcome(), None);
+
+        play_san(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(game.repetitions(), 4);
+        assert_eq!(game.outcome(), None);
+
+        play_san(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::FivefoldRepetition))
+        );
+        assert!(!game.claim_draw());
+
+        let mut game = Game::new();
+        play_san(
+            &mut game,
+            &["Nc3", "Nc6", "Nb1", "Nb8", "Nc3", "Nc6", "Nb1"],
+        );
+        assert!(!game.claim_draw());
+        play_san(&mut game, &["Nb8"]);
+        assert!(game.claim_draw());
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::ThreefoldRepetition))
+        );
+    }
+
+    #[test]
+    fn repetition_needs_same_castling_rights() {
+        let mut game = Game::new();
+        play_san(&mut game, &["e4", "e5", "Ke2", "Ke7", "Ke1", "Ke8"]);
+
+        // the pieces stand as they did after 1. e4 e5, but castling is no longer possible
+        assert_eq!(game.repetitions(), 1);
+
+        play_san(&mut game, &["Ke2", "Ke7", "Ke1", "Ke8"]);
+        assert_eq!(game.repetitions(), 2);
+        play_san(&mut game, &["Ke2", "Ke7", "Ke1", "Ke8"]);
+        assert!(game.is_threefold_repetition());
+    }
+
+    #[test]
+    fn repetition_needs_same_en_passant_rights() {
+        let board = Board::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        let mut game = Game::from_board(board);
+
+        // black could take en passant right after the double push, but not later
+        play_san(&mut game, &["e4", "Kd7", "Kd1", "Ke8", "Ke1"]);
+        assert_eq!(game.repetitions(), 1);
+
+        play_san(&mut game, &["Kd7", "Kd1", "Ke8", "Ke1"]);
+        assert_eq!(game.repetitions(), 2);
+        play_san(&mut game, &["Kd7", "Kd1", "Ke8", "Ke1"]);
+        assert!(game.is_threefold_repetition());
+
+        // bxc6 would leave the white king in check from the rook, so it was never possible
+        let board = Board::from_fen("4k3/2p5/8/KP5r/8/8/8/8 b - - 0 1").unwrap();
+        let mut game = Game::from_board(board);
+        play_san(&mut game, &["c5", "Ka4", "Kd8", "Ka5", "Ke8"]);
+        assert_eq!(game.repetitions(), 2);
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let board = Board::from_fen("7k/8/8/8/8/8/8/KR6 w - - 98 80").unwrap();
+        let mut game = Game::from_board(board);
+
+        play_san(&mut game, &["Rb2"]);
+        assert_eq!(game.halfmove_clock(), 99);
+        assert!(!game.is_fifty_move_rule());
+        assert!(!game.claim_draw());
+
+        play_san(&mut game, &["Kg8"]);
+        assert!(game.is_fifty_move_rule());
+        assert_eq!(game.outcome(), None);
+        assert!(game.claim_draw());
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::FiftyMoveRule))
+        );
+
+        // a pawn move starts the count again
+        let board = Board::from_fen("7k/p7/8/8/8/8/8/KR6 b - - 99 80").unwrap();
+        let mut game = Game::from_board(board);
+        play_san(&mut game, &["a6"]);
+        assert_eq!(game.halfmove_clock(), 0);
+        assert!(!game.claim_draw());
+
+        // and so does a capture
+        let board = Board::from_fen("7k/8/8/8/8/8/1r6/KR6 w - - 99 80").unwrap();
+        let mut game = Game::from_board(board);
+        play_san(&mut game, &["Rxb2"]);
+        assert_eq!(game.halfmove_clock(), 0);
+        assert!(!game.is_fifty_move_rule());
+    }
+
+    #[test]
+    fn seventy_five_move_rule() {
+        let board = Board::from_fen("7k/8/8/8/8/8/8/KR6 w - - 148 100").unwrap();
+        let mut game = Game::from_board(board);
+
+        play_san(&mut game, &["Rb2"]);
+        assert_eq!(game.outcome(), None);
+        play_san(&mut game, &["Kg8"]);
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::SeventyFiveMoveRule))
+        );
+
+        // mate on the last move still wins
+        let board = Board::from_fen("7k/8/6K1/8/8/8/8/R7 w - - 149 100").unwrap();
+        let mut game = Game::from_board(board);
+        play_san(&mut game, &["Ra8#"]);
+        assert_eq!(game.outcome(), Some(GameOutcome::WhiteWins));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let board = Board::from_fen("8/8/4k3/8/8/3K4/8/8 w - - 0 1").unwrap();
+        assert_eq!(
+            Game::from_board(board).outcome(),
+            Some(GameOutcome::Draw(DrawReason::InsufficientMaterial))
+        );
+
+        // taking the last rook leaves a knight, which cannot mate on its own
+        let board = Board::from_fen("8/8/4k3/8/8/3K1N2/8/6r1 w - - 0 1").unwrap();
+        let mut game = Game::from_board(board);
+        assert_eq!(game.outcome(), None);
+        play_san(&mut game, &["Nxg1"]);
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::InsufficientMaterial))
+        );
+    }
+
+    #[test]
+    fn dead_position() {
+        // only the kings can move, and the pawns keep them apart
+        let board = Board::from_fen("8/4k3/8/p1p1p1p1/P1P1P1P1/8/4K3/8 w - - 0 1").unwrap();
+        let mut game = Game::from_board(board);
+        assert_eq!(game.outcome(), None);
+
+        game.set_detect_dead_positions(true);
+        assert_eq!(
+            game.outcome(),
+            Some(GameOutcome::Draw(DrawReason::DeadPosition))
+        );
+    }
+}
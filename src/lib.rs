@@ -1,7 +1,18 @@
-mod board;
-mod position;
-// mod game;
-mod piece;
+pub mod bitboard;
+pub mod board;
+pub mod eval;
+pub mod fen;
+pub mod game;
+pub mod magic;
+pub mod moves;
+pub mod perft;
+pub mod pgn;
+pub mod piece;
+pub mod position;
+pub mod san;
+pub mod search;
+pub mod uci;
+mod zobrist;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,215 @@
+use std::sync::OnceLock;
+
+use crate::bitboard::{ray_attacks, shift, Bitboard, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+use crate::position::Position;
+
+/// Finds the attacks of a rook or bishop on one square with a single multiplication:
+/// the occupied squares that can block it, times `magic`, shifted down, index
+/// straight into a table of precomputed attack sets.
+#[derive(Clone, Copy, Debug, Default)]
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    const fn index(&self, occupied: u64) -> usize {
+        self.offset + (((occupied & self.mask).wrapping_mul(self.magic)) >> self.shift) as usize
+    }
+}
+
+struct Table {
+    magics: [Magic; 64],
+    attacks: Vec<Bitboard>,
+}
+
+impl Table {
+    /// Searches for a magic number for every square. The random numbers come from fixed
+    /// seeds, so every run builds the same tables.
+    fn new(directions: [(i8, i8); 4]) -> Self {
+        let mut magics = [Magic::default(); 64];
+        let mut attacks = vec![];
+
+        for (index, magic) in magics.iter_mut().enumerate() {
+            let position = Position::from_index(index as u8);
+            let mask = relevant_occupancy(position, directions);
+            let bits = mask.count_ones();
+            let mut random = Random(SEEDS[index / 8]);
+
+            let occupancies: Vec<u64> = subsets(mask).collect();
+            let reference: Vec<Bitboard> = occupancies
+                .iter()
+                .map(|occupied| ray_attacks(position, Bitboard(*occupied), directions))
+                .collect();
+
+            let mut table = vec![Bitboard::EMPTY; 1 << bits];
+            // which attempt last wrote each entry, so the table never needs clearing
+            let mut written = vec![0; 1 << bits];
+            let mut attempt = 0;
+
+            *magic = loop {
+                let candidate = Magic {
+                    mask,
+                    magic: random.sparse(),
+                    shift: 64 - bits,
+                    offset: attacks.len(),
+                };
+
+                // multiplying the mask has to fill the top bits for the index to be useful
+                if (mask.wrapping_mul(candidate.magic) >> 56).count_ones() < 6 {
+                    continue;
+                }
+
+                attempt += 1;
+
+                let fits = occupancies
+                    .iter()
+                    .zip(&reference)
+                    .all(|(occupied, attack)| {
+                        let slot = candidate.index(*occupied) - candidate.offset;
+
+                        if written[slot] == attempt {
+                            table[slot] == *attack
+                        } else {
+                            written[slot] = attempt;
+                            table[slot] = *attack;
+                            true
+                        }
+                    });
+
+                if fits {
+                    break candidate;
+                }
+            };
+
+            attacks.extend(table);
+        }
+
+        Table { magics, attacks }
+    }
+
+    fn attacks(&self, position: Position, occupied: Bitboard) -> Bitboard {
+        match position.index() {
+            Some(index) => self.attacks[self.magics[index as usize].index(occupied.0)],
+            None => Bitboard::EMPTY,
+        }
+    }
+}
+
+/// The squares whose occupancy can change the attacks from `position`: every ray
+/// without its last square, as a piece on the edge never blocks anything further out.
+fn relevant_occupancy(position: Position, directions: [(i8, i8); 4]) -> u64 {
+    let mut mask = 0;
+
+    for (dx, dy) in directions {
+        let mut square = shift(Bitboard::from_position(position).0, dx, dy);
+
+        while shift(square, dx, dy) != 0 {
+            mask |= square;
+            square = shift(square, dx, dy);
+        }
+    }
+
+    mask
+}
+
+/// Every subset of `mask`, starting with the empty one.
+fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    let mut subset = Some(0u64);
+
+    std::iter::from_fn(move || {
+        let current = subset?;
+        let next = current.wrapping_sub(mask) & mask;
+        subset = if next == 0 { None } else { Some(next) };
+        Some(current)
+    })
+}
+
+/// Seeds for each rank that are known to turn up magics after few attempts.
+const SEEDS: [u64; 8] = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+
+/// xorshift64*, only used to try out magic numbers.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number with few bits set, which makes a good magic far more likely.
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+fn rook_table() -> &'static Table {
+    static TABLE: OnceLock<Table> = OnceLock::new();
+    TABLE.get_or_init(|| Table::new(ROOK_DIRECTIONS))
+}
+
+fn bishop_table() -> &'static Table {
+    static TABLE: OnceLock<Table> = OnceLock::new();
+    TABLE.get_or_init(|| Table::new(BISHOP_DIRECTIONS))
+}
+
+/// The squares a rook on `position` attacks, up to and including the first piece in
+/// each direction. The tables are built the first time this is called.
+pub fn rook_attacks(position: Position, occupied: Bitboard) -> Bitboard {
+    rook_table().attacks(position, occupied)
+}
+
+/// The squares a bishop on `position` attacks, up to and including the first piece in
+/// each direction. The tables are built the first time this is called.
+pub fn bishop_attacks(position: Position, occupied: Bitboard) -> Bitboard {
+    bishop_table().attacks(position, occupied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relevant_occupancy_masks() {
+        let a1 = Position::new(0, 0);
+        assert_eq!(relevant_occupancy(a1, ROOK_DIRECTIONS).count_ones(), 12);
+        assert_eq!(relevant_occupancy(a1, BISHOP_DIRECTIONS).count_ones(), 6);
+
+        let e4 = Position::new(4, 3);
+        assert_eq!(relevant_occupancy(e4, ROOK_DIRECTIONS).count_ones(), 10);
+        assert_eq!(relevant_occupancy(e4, BISHOP_DIRECTIONS).count_ones(), 9);
+
+        assert_eq!(subsets(0b101).collect::<Vec<u64>>(), vec![0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn matches_ray_walking() {
+        let mut random = Random(1);
+
+        for index in 0..64 {
+            let position = Position::from_index(index);
+
+            for _ in 0..64 {
+                let occupied = Bitboard(random.sparse() | random.sparse());
+
+                assert_eq!(
+                    rook_attacks(position, occupied),
+                    ray_attacks(position, occupied, ROOK_DIRECTIONS)
+                );
+                assert_eq!(
+                    bishop_attacks(position, occupied),
+                    ray_attacks(position, occupied, BISHOP_DIRECTIONS)
+                );
+            }
+        }
+
+        assert_eq!(
+            rook_attacks(Position::new(8, 0), Bitboard::ALL),
+            Bitboard::EMPTY
+        );
+    }
+}
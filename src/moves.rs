@@ -0,0 +1,83 @@
+use crate::piece::PieceKind;
+use crate::position::Position;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Move {
+    from: Position,
+    to: Position,
+    kind: MoveKind,
+    captured: Option<PieceKind>,
+    promotion: Option<PieceKind>,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum MoveKind {
+    Normal,
+    DoublePawnPush,
+    EnPassant,
+    KingSideCastle,
+    QueenSideCastle,
+}
+
+impl Move {
+    pub const fn new(from: Position, to: Position) -> Self {
+        Self {
+            from,
+            to,
+            kind: MoveKind::Normal,
+            captured: None,
+            promotion: None,
+        }
+    }
+
+    pub const fn with_kind(self, kind: MoveKind) -> Self {
+        Self { kind, ..self }
+    }
+
+    pub const fn with_captured(self, captured: PieceKind) -> Self {
+        Self {
+            captured: Some(captured),
+            ..self
+        }
+    }
+
+    /// Turns the pawn making this move into `kind` once it reaches the back rank.
+    pub const fn with_promotion(self, kind: PieceKind) -> Self {
+        Self {
+            promotion: Some(kind),
+            ..self
+        }
+    }
+
+    pub const fn from(&self) -> Position {
+        self.from
+    }
+
+    pub const fn to(&self) -> Position {
+        self.to
+    }
+
+    pub const fn kind(&self) -> MoveKind {
+        self.kind
+    }
+
+    /// The kind of piece this move takes, including a pawn taken en passant.
+    pub const fn captured(&self) -> Option<PieceKind> {
+        self.captured
+    }
+
+    pub const fn promotion(&self) -> Option<PieceKind> {
+        self.promotion
+    }
+
+    pub const fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    pub const fn is_castle(&self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::KingSideCastle | MoveKind::QueenSideCastle
+        )
+    }
+}
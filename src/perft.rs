@@ -0,0 +1,156 @@
+use crate::board::Board;
+use crate::moves::Move;
+
+impl Board {
+    /// Counts the positions reached by playing every sequence of `depth` legal moves.
+    /// Comparing the count with published numbers is the standard way to check
+    /// a move generator.
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_in_place(depth)
+    }
+
+    /// `perft` on a board that is walked through the tree with `make_move` and
+    /// `unmake_move`, and ends up as it started.
+    fn perft_in_place(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+
+        // the moves at the last ply only need counting, not playing
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .into_iter()
+            .map(|mv| {
+                let undo = self.make_move(mv);
+                let nodes = self.perft_in_place(depth - 1);
+                self.unmake_move(undo);
+                nodes
+            })
+            .sum()
+    }
+
+    /// `perft` split up by the first move, sorted by the moves' UCI notation, so that
+    /// a count that differs from another engine's can be tracked down move by move.
+    /// At depth 0 no move is played, so there is nothing to split up and the result
+    /// is empty.
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return vec![];
+        }
+
+        let mut board = self.clone();
+
+        let mut divide: Vec<(Move, u64)> = self
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let undo = board.make_move(mv);
+                let nodes = board.perft_in_place(depth - 1);
+                board.unmake_move(undo);
+                (mv, nodes)
+            })
+            .collect();
+
+        divide.sort_by_key(|(mv, _)| mv.to_uci());
+
+        divide
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::STARTING_FEN;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected: &[u64]) {
+        let board = Board::from_fen(fen).unwrap();
+
+        for (depth, expected) in expected.iter().enumerate() {
+            assert_eq!(
+                board.perft(depth as u32 + 1),
+                *expected,
+                "perft({}) of {}",
+                depth + 1,
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn starting_position() {
+        assert_perft(STARTING_FEN, &[20, 400, 8902]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2039]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2812]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9467]);
+
+        // the same position with colors swapped
+        assert_perft(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9467],
+        );
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, &[44, 1486]);
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(POSITION_6, &[46, 2079]);
+    }
+
+    #[test]
+    #[ignore = "slow; run with --ignored, preferably in release mode"]
+    fn deep() {
+        assert_perft(STARTING_FEN, &[20, 400, 8902, 197281, 4865609]);
+        assert_perft(KIWIPETE, &[48, 2039, 97862, 4085603]);
+        assert_perft(POSITION_3, &[14, 191, 2812, 43238, 674624, 11030083]);
+        assert_perft(POSITION_4, &[6, 264, 9467, 422333]);
+        assert_perft(POSITION_5, &[44, 1486, 62379, 2103487]);
+        assert_perft(POSITION_6, &[46, 2079, 89890, 3894594]);
+    }
+
+    #[test]
+    fn divide() {
+        let board = Board::default();
+        let divide = board.divide(2);
+
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 400);
+        assert_eq!(divide[0].0.to_uci(), "a2a3");
+        assert!(divide.iter().all(|(_, nodes)| *nodes == 20));
+
+        let board = Board::from_fen(KIWIPETE).unwrap();
+        let divide = board.divide(1);
+        assert_eq!(divide.len(), 48);
+        assert!(divide.iter().all(|(_, nodes)| *nodes == 1));
+
+        assert!(board.divide(0).is_empty());
+        assert_eq!(board.perft(0), 1);
+    }
+}
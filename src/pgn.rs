@@ -0,0 +1,1081 @@
+use std::fmt::Display;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::fen::{FenError, STARTING_FEN};
+use crate::game::{Game, GameOutcome};
+use crate::moves::Move;
+use crate::piece::Color;
+use crate::san::SanError;
+
+/// The tags every exported game starts with, in the order they are written.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// Movetext lines are wrapped so that none is longer than this.
+const LINE_WIDTH: usize = 80;
+
+/// A game read from, or to be written as, Portable Game Notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnGame {
+    /// Tag pairs in the order they appeared.
+    pub tags: Vec<(String, String)>,
+    /// Comments before the first move.
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: PgnResult,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// Numeric annotation glyphs, with suffixes like "!?" turned into their `$n` equivalents.
+    pub nags: Vec<u8>,
+    /// Comments following the move.
+    pub comments: Vec<String>,
+    /// Alternatives to this move, each starting from the position before it.
+    pub variations: Vec<Variation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variation {
+    /// Comments before the first move of the variation.
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum PgnResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// The game is still going on, or its result is not known: "*".
+    Unknown,
+}
+
+impl PgnResult {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            PgnResult::WhiteWins => "1-0",
+            PgnResult::BlackWins => "0-1",
+            PgnResult::Draw => "1/2-1/2",
+            PgnResult::Unknown => "*",
+        }
+    }
+}
+
+impl FromStr for PgnResult {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(PgnResult::WhiteWins),
+            "0-1" => Ok(PgnResult::BlackWins),
+            "1/2-1/2" => Ok(PgnResult::Draw),
+            "*" => Ok(PgnResult::Unknown),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for PgnResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<Option<GameOutcome>> for PgnResult {
+    fn from(outcome: Option<GameOutcome>) -> Self {
+        match outcome {
+            Some(GameOutcome::WhiteWins) => PgnResult::WhiteWins,
+            Some(GameOutcome::BlackWins) => PgnResult::BlackWins,
+            Some(GameOutcome::Draw(_)) => PgnResult::Draw,
+            None => PgnResult::Unknown,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(std::io::Error),
+    /// `line` counts from 1 at the start of the input.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    Fen(FenError),
+    Move {
+        line: usize,
+        error: SanError,
+    },
+}
+
+impl Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::Io(error) => write!(f, "could not read PGN: {}", error),
+            PgnError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            PgnError::Fen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnError::Move { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<std::io::Error> for PgnError {
+    fn from(error: std::io::Error) -> Self {
+        PgnError::Io(error)
+    }
+}
+
+impl From<FenError> for PgnError {
+    fn from(error: FenError) -> Self {
+        PgnError::Fen(error)
+    }
+}
+
+impl PgnGame {
+    /// Parses a single game. Use `PgnReader` for input holding more than one.
+    pub fn parse(pgn: &str) -> Result<Self, PgnError> {
+        parse_game(pgn, 1)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces the value of the tag called `name`, or adds the tag if it is missing.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// The position the game starts from: the `FEN` tag if there is one,
+    /// otherwise the standard starting position.
+    pub fn starting_board(&self) -> Result<Board, FenError> {
+        match self.tag("FEN") {
+            Some(fen) => Board::from_fen(fen),
+            None => Ok(Board::default()),
+        }
+    }
+
+    /// The position after the last move of the main line.
+    pub fn board(&self) -> Result<Board, FenError> {
+        let mut board = self.starting_board()?;
+
+        for pgn_move in &self.moves {
+            board.play(pgn_move.mv);
+        }
+
+        Ok(board)
+    }
+
+    /// Writes the game in PGN export format: the seven tag roster ("?" for tags that
+    /// are missing) followed by any other tags, then the movetext wrapped at 80 columns
+    /// and ending with the result. The moves have to be legal.
+    pub fn to_pgn(&self) -> Result<String, FenError> {
+        let mut pgn = String::new();
+
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => self.result.as_str(),
+                "Date" => self.tag(name).unwrap_or("????.??.??"),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+            write_tag(&mut pgn, name, value);
+        }
+
+        for (name, value) in &self.tags {
+            if SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+                continue;
+            }
+            if name == "FEN" && self.tag("SetUp").is_none() {
+                write_tag(&mut pgn, "SetUp", "1");
+            }
+            write_tag(&mut pgn, name, value);
+        }
+
+        let mut movetext = Movetext::default();
+        movetext.line(&self.starting_board()?, &self.comments, &self.moves);
+        movetext.push(self.result.as_str().to_owned());
+
+        pgn.push('\n');
+        pgn.push_str(&movetext.wrap());
+        pgn.push('\n');
+
+        Ok(pgn)
+    }
+}
+
+impl From<&Game> for PgnGame {
+    /// The game's moves and result, with `SetUp` and `FEN` tags if it did not start
+    /// from the standard position. Other tags are left for the caller to fill in.
+    fn from(game: &Game) -> Self {
+        let mut tags = vec![];
+
+        let fen = game.starting_board().to_fen();
+        if fen != STARTING_FEN {
+            tags.push(("SetUp".to_owned(), "1".to_owned()));
+            tags.push(("FEN".to_owned(), fen));
+        }
+
+        let moves = game
+            .moves()
+            .iter()
+            .map(|&mv| PgnMove {
+                mv,
+                nags: vec![],
+                comments: vec![],
+                variations: vec![],
+            })
+            .collect();
+
+        PgnGame {
+            tags,
+            comments: vec![],
+            moves,
+            result: game.outcome().into(),
+        }
+    }
+}
+
+fn write_tag(pgn: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+}
+
+/// Movetext split into the pieces that may not be broken across lines.
+#[derive(Default)]
+struct Movetext {
+    tokens: Vec<String>,
+    /// Set after "(" so that it sticks to the token that follows.
+    open_parens: usize,
+}
+
+impl Movetext {
+    fn push(&mut self, token: String) {
+        let parens = "(".repeat(self.open_parens);
+        self.open_parens = 0;
+        self.tokens.push(parens + &token);
+    }
+
+    fn comment(&mut self, comment: &str) {
+        let words: Vec<&str> = comment.split_whitespace().collect();
+
+        // a brace comment cannot hold "}", but a rest-of-line comment can
+        if comment.contains('}') {
+            self.push(format!(";{}\n", words.join(" ")));
+            return;
+        }
+
+        match words.as_slice() {
+            [] => self.push("{}".to_owned()),
+            [word] => self.push(format!("{{{}}}", word)),
+            [first, middle @ .., last] => {
+                self.push(format!("{{{}", first));
+                for word in middle {
+                    self.push(word.to_string());
+                }
+                self.push(format!("{}}}", last));
+            }
+        }
+    }
+
+    /// Writes `moves` as played from `board`, numbering white moves and any black move
+    /// that does not directly follow a white one.
+    fn line(&mut self, board: &Board, comments: &[String], moves: &[PgnMove]) {
+        let mut board = board.clone();
+        let mut needs_number = true;
+
+        for comment in comments {
+            self.comment(comment);
+        }
+
+        for pgn_move in moves {
+            let before = board.clone();
+
+            // the number is kept on the same line as the move it belongs to
+            let number = match board.side_to_move() {
+                Color::White => format!("{}. ", board.fullmove_number()),
+                Color::Black if needs_number => format!("{}... ", board.fullmove_number()),
+                Color::Black => String::new(),
+            };
+            self.push(number + &board.to_san(pgn_move.mv));
+            board.play(pgn_move.mv);
+
+            for nag in &pgn_move.nags {
+                self.push(format!("${}", nag));
+            }
+            for comment in &pgn_move.comments {
+                self.comment(comment);
+            }
+            for variation in &pgn_move.variations {
+                let written = self.tokens.len();
+                self.open_parens += 1;
+                self.line(&before, &variation.comments, &variation.moves);
+
+                if self.tokens.len() == written {
+                    self.open_parens -= 1;
+                    self.push("()".to_owned());
+                } else if let Some(last) = self.tokens.last_mut() {
+                    last.push(')');
+                }
+            }
+
+            needs_number = !pgn_move.comments.is_empty() || !pgn_move.variations.is_empty();
+        }
+    }
+
+    fn wrap(&self) -> String {
+        let mut text = String::new();
+        let mut line_length = 0;
+
+        for token in &self.tokens {
+            if line_length > 0 && line_length + 1 + token.len() > LINE_WIDTH {
+                text.push('\n');
+                line_length = 0;
+            }
+            if line_length > 0 {
+                text.push(' ');
+                line_length += 1;
+            }
+            text.push_str(token);
+            line_length += token.len();
+
+            // a rest-of-line comment ends the line it is on
+            if token.ends_with('\n') {
+                line_length = 0;
+            }
+        }
+
+        text
+    }
+}
+
+/// Reads games one at a time from PGN input, so that large multi-game files
+/// never have to be held in memory all at once.
+pub struct PgnReader<R> {
+    reader: R,
+    /// The first line of the next game, read while looking for the end of the previous one.
+    pending: Option<String>,
+    line: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: None,
+            line: 1,
+        }
+    }
+
+    /// Collects the text of the next game, returning it with the line it starts on.
+    /// A game ends with its result, or failing that where the next one's tags begin.
+    fn next_game_text(&mut self) -> Result<Option<(String, usize)>, std::io::Error> {
+        let mut text = String::new();
+        let mut start = self.line;
+        let mut in_movetext = false;
+        let mut has_tags = false;
+        let mut in_comment = false;
+        let mut variation_depth = 0usize;
+
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => {
+                    let mut line = String::new();
+                    if self.reader.read_line(&mut line)? == 0 {
+                        break;
+                    }
+                    line
+                }
+            };
+
+            let trimmed = line.trim_start();
+
+            if !in_comment && in_movetext && trimmed.starts_with('[') {
+                self.pending = Some(line);
+                break;
+            }
+
+            let is_tag = !in_comment && trimmed.starts_with('[');
+
+            // comments ahead of the first tags belong to the file rather than the game
+            if is_tag && !has_tags {
+                text.clear();
+                start = self.line;
+            }
+            has_tags |= is_tag;
+
+            // braces in a quoted tag value, like [Event "a {b"], do not open a comment
+            let mut in_string = false;
+            let mut escaped = false;
+            let mut word_start = None;
+            // where the game's result ends, if it is on this line
+            let mut end = None;
+            let is_result =
+                |word: &str, depth: usize| depth == 0 && word.parse::<PgnResult>().is_ok();
+
+            let escape = !in_comment && line.starts_with('%');
+
+            for (index, c) in line.char_indices().filter(|_| !escape) {
+                if in_string {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => in_string = false,
+                        _ => (),
+                    }
+                    continue;
+                }
+
+                if in_comment {
+                    in_comment = c != '}';
+                    continue;
+                }
+
+                // comments alone, like one at the top of a file, do not start the movetext
+                if !is_tag && !c.is_whitespace() && c != '{' && c != ';' {
+                    in_movetext = true;
+                }
+
+                if !c.is_whitespace() && !"{}()[];\"".contains(c) {
+                    word_start.get_or_insert(index);
+                    continue;
+                }
+
+                if let Some(word_start) = word_start.take() {
+                    if in_movetext && is_result(&line[word_start..index], variation_depth) {
+                        end = Some(index);
+                        break;
+                    }
+                }
+
+                match c {
+                    '{' => in_comment = true,
+                    '"' => in_string = true,
+                    '(' => variation_depth += 1,
+                    ')' => variation_depth = variation_depth.saturating_sub(1),
+                    ';' => break,
+                    _ => (),
+                }
+            }
+
+            // a result right at the end of input without a newline
+            if let Some(word_start) = word_start {
+                if end.is_none() && in_movetext && is_result(&line[word_start..], variation_depth) {
+                    end = Some(line.len());
+                }
+            }
+
+            match end {
+                // whatever follows the result on the same line belongs to the next game
+                Some(end) if !line[end..].trim().is_empty() => {
+                    text.push_str(&line[..end]);
+                    self.pending = Some(line[end..].to_owned());
+                    break;
+                }
+                Some(_) => {
+                    self.line += 1;
+                    text.push_str(&line);
+                    break;
+                }
+                None => {
+                    self.line += 1;
+                    text.push_str(&line);
+                }
+            }
+        }
+
+        // nothing but comments and blank lines is not a game
+        if !in_movetext && !has_tags {
+            Ok(None)
+        } else {
+            Ok(Some((text, start)))
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_game_text() {
+            Ok(Some((text, start))) => Some(parse_game(&text, start)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error.into())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    String(String),
+    Comment(String),
+    Nag(u8),
+    Symbol(String),
+}
+
+fn tokenize(text: &str, start_line: usize) -> Result<Vec<(Token, usize)>, PgnError> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    let mut line = start_line;
+    let mut at_line_start = true;
+
+    let syntax = |line: usize, message: &str| PgnError::Syntax {
+        line,
+        message: message.to_owned(),
+    };
+
+    while let Some(c) = chars.next() {
+        let token_line = line;
+        let was_at_line_start = at_line_start;
+        at_line_start = c == '\n';
+
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => at_line_start = was_at_line_start,
+            '%' if was_at_line_start => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        at_line_start = true;
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                let mut comment = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        at_line_start = true;
+                        break;
+                    }
+                    comment.push(c);
+                }
+                tokens.push((Token::Comment(comment.trim().to_owned()), token_line));
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            comment.push(c);
+                        }
+                        None => return Err(syntax(token_line, "unterminated comment")),
+                    }
+                }
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                tokens.push((Token::Comment(comment), token_line));
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => string.push(c),
+                            None => return Err(syntax(token_line, "unterminated string")),
+                        },
+                        Some('\n') | None => return Err(syntax(token_line, "unterminated string")),
+                        Some(c) => string.push(c),
+                    }
+                }
+                tokens.push((Token::String(string), token_line));
+            }
+            '[' => tokens.push((Token::LeftBracket, token_line)),
+            ']' => tokens.push((Token::RightBracket, token_line)),
+            '(' => tokens.push((Token::LeftParen, token_line)),
+            ')' => tokens.push((Token::RightParen, token_line)),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                    digits.push(c);
+                }
+                let nag = digits
+                    .parse()
+                    .map_err(|_| syntax(token_line, "invalid NAG"))?;
+                tokens.push((Token::Nag(nag), token_line));
+            }
+            c if c.is_ascii_alphanumeric() || c == '*' => {
+                let mut symbol = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|c| c.is_ascii_alphanumeric() || "_+#=:-/.!?".contains(*c))
+                {
+                    symbol.push(c);
+                }
+                tokens.push((Token::Symbol(symbol), token_line));
+            }
+            c => {
+                return Err(syntax(token_line, &format!("unexpected character '{}'", c)));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Splits the "!?"-style suffix annotation off a move, returning the matching NAG.
+fn split_suffix_annotation(symbol: &str) -> (&str, Option<u8>) {
+    let san = symbol.trim_end_matches(['!', '?']);
+
+    let nag = match &symbol[san.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+
+    (san, nag)
+}
+
+/// Strips a leading move number such as "12." or "12..." from a symbol.
+fn strip_move_number(symbol: &str) -> &str {
+    let without_digits = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    if without_digits.len() < symbol.len() && without_digits.starts_with('.') {
+        without_digits.trim_start_matches('.')
+    } else {
+        symbol
+    }
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<(Token, usize)>>,
+    last_line: usize,
+}
+
+impl Parser {
+    fn syntax(&self, line: usize, message: &str) -> PgnError {
+        PgnError::Syntax {
+            line,
+            message: message.to_owned(),
+        }
+    }
+
+    fn tags(&mut self) -> Result<Vec<(String, String)>, PgnError> {
+        let mut tags = vec![];
+
+        while let Some((Token::LeftBracket, line)) = self.tokens.peek().cloned() {
+            self.tokens.next();
+
+            let name = match self.tokens.next() {
+                Some((Token::Symbol(name), _)) => name,
+                _ => return Err(self.syntax(line, "expected a tag name")),
+            };
+            let value = match self.tokens.next() {
+                Some((Token::String(value), _)) => value,
+                _ => return Err(self.syntax(line, "expected a quoted tag value")),
+            };
+            match self.tokens.next() {
+                Some((Token::RightBracket, _)) => (),
+                _ => return Err(self.syntax(line, "expected ']' after the tag value")),
+            }
+
+            tags.push((name, value));
+        }
+
+        Ok(tags)
+    }
+
+    /// Parses moves starting from `board` until the end of the variation or game.
+    /// Returns the game result too if one ended the line.
+    fn line(
+        &mut self,
+        board: &Board,
+        in_variation: bool,
+    ) -> Result<(Variation, Option<PgnResult>), PgnError> {
+        let mut board = board.clone();
+        let mut before_last_move = board.clone();
+        let mut comments = vec![];
+        let mut moves: Vec<PgnMove> = vec![];
+
+        while let Some((token, line)) = self.tokens.next() {
+            self.last_line = line;
+
+            match token {
+                Token::Symbol(symbol) => {
+                    if let Ok(result) = symbol.parse::<PgnResult>() {
+                        if in_variation {
+                            return Err(self.syntax(line, "game result inside a variation"));
+                        }
+                        return Ok((Variation { comments, moves }, Some(result)));
+                    }
+
+                    let symbol = strip_move_number(&symbol);
+                    if symbol.is_empty() {
+                        continue;
+                    }
+
+                    let (san, nag) = split_suffix_annotation(symbol);
+                    let mv = board
+                        .parse_san(san)
+                        .map_err(|error| PgnError::Move { line, error })?;
+
+                    before_last_move = board.clone();
+                    board.play(mv);
+
+                    moves.push(PgnMove {
+                        mv,
+                        nags: nag.into_iter().collect(),
+                        comments: vec![],
+                        variations: vec![],
+                    });
+                }
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(last) => last.nags.push(nag),
+                    None => return Err(self.syntax(line, "NAG before the first move")),
+                },
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(last) => last.comments.push(comment),
+                    None => comments.push(comment),
+                },
+                Token::LeftParen => {
+                    if moves.is_empty() {
+                        return Err(self.syntax(line, "variation before the first move"));
+                    }
+
+                    let (variation, _) = self.line(&before_last_move, true)?;
+                    if let Some(last) = moves.last_mut() {
+                        last.variations.push(variation);
+                    }
+                }
+                Token::RightParen => {
+                    if in_variation {
+                        return Ok((Variation { comments, moves }, None));
+                    }
+                    return Err(self.syntax(line, "unmatched ')'"));
+                }
+                Token::LeftBracket | Token::RightBracket | Token::String(_) => {
+                    return Err(self.syntax(line, "unexpected tag in the movetext"));
+                }
+            }
+        }
+
+        if in_variation {
+            return Err(self.syntax(self.last_line, "unterminated variation"));
+        }
+
+        Ok((Variation { comments, moves }, None))
+    }
+}
+
+fn parse_game(text: &str, start_line: usize) -> Result<PgnGame, PgnError> {
+    let mut parser = Parser {
+        tokens: tokenize(text, start_line)?.into_iter().peekable(),
+        last_line: start_line,
+    };
+
+    let tags = parser.tags()?;
+
+    let mut game = PgnGame {
+        tags,
+        comments: vec![],
+        moves: vec![],
+        result: PgnResult::Unknown,
+    };
+
+    let board = game.starting_board()?;
+    let (main_line, result) = parser.line(&board, false)?;
+
+    if let Some((_, line)) = parser.tokens.next() {
+        return Err(parser.syntax(line, "text after the game result"));
+    }
+
+    game.comments = main_line.comments;
+    game.moves = main_line.moves;
+    game.result = result
+        .or_else(|| game.tag("Result").and_then(|result| result.parse().ok()))
+        .unwrap_or(PgnResult::Unknown);
+
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::PieceKind;
+
+    const IMMORTAL: &str = r#"[Event "London"]
+[Site "London ENG"]
+[Date "1851.06.21"]
+[Round "?"]
+[White "Adolf Anderssen"]
+[Black "Lionel Kieseritzky"]
+[Result "1-0"]
+
+1.e4 e5 2.f4 exf4 3.Bc4 Qh4+ 4.Kf1 b5 5.Bxb5 Nf6 6.Nf3 Qh6 7.d3 Nh5 8.Nh4 Qg5
+9.Nf5 c6 10.g4 Nf6 11.Rg1 cxb5 12.h4 Qg6 13.h5 Qg5 14.Qf3 Ng8 15.Bxf4 Qf6
+16.Nc3 Bc5 17.Nd5 Qxb2 18.Bd6 Bxg1 19.e5 Qxa1+ 20.Ke2 Na6 21.Nxg7+ Kd8
+22.Qf6+ Nxf6 23.Be7# 1-0
+"#;
+
+    #[test]
+    fn parse() {
+        let game = PgnGame::parse(IMMORTAL).unwrap();
+
+        assert_eq!(game.tag("White"), Some("Adolf Anderssen"));
+        assert_eq!(game.tag("Date"), Some("1851.06.21"));
+        assert_eq!(game.tag("FEN"), None);
+        assert_eq!(game.tags.len(), 7);
+        assert_eq!(game.moves.len(), 45);
+        assert_eq!(game.result, PgnResult::WhiteWins);
+
+        let board = game.board().unwrap();
+        assert!(board.is_checkmate());
+        assert_eq!(
+            board.to_fen(),
+            "r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23"
+        );
+    }
+
+    #[test]
+    fn annotations() {
+        let pgn = r#"[Event "?"]
+
+{ An opening } 1. e4 $1 e5!? { solid } 2. Nf3 (2. f4 {the gambit} exf4 (2... d5) 3. Nf3)
+(2. Bc4) 2... Nc6 ; end of line comment
+3. Bb5 a6 *"#;
+
+        let game = PgnGame::parse(pgn).unwrap();
+
+        assert_eq!(game.comments, vec!["An opening".to_owned()]);
+        assert_eq!(game.moves.len(), 6);
+        assert_eq!(game.moves[0].nags, vec![1]);
+        assert_eq!(game.moves[1].nags, vec![5]);
+        assert_eq!(game.moves[1].comments, vec!["solid".to_owned()]);
+        assert_eq!(
+            game.moves[3].comments,
+            vec!["end of line comment".to_owned()]
+        );
+        assert_eq!(game.result, PgnResult::Unknown);
+
+        let variations = &game.moves[2].variations;
+        assert_eq!(variations.len(), 2);
+        assert_eq!(variations[0].moves.len(), 3);
+        assert_eq!(
+            variations[0].moves[0].comments,
+            vec!["the gambit".to_owned()]
+        );
+        assert_eq!(variations[0].moves[1].mv.captured(), Some(PieceKind::Pawn));
+        assert_eq!(variations[0].moves[1].variations[0].moves.len(), 1);
+        assert_eq!(variations[1].moves[0].mv.to(), "c4".parse().unwrap());
+    }
+
+    #[test]
+    fn set_up() {
+        let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 40"]
+[Result "*"]
+
+40. e4 Kd7 41. e5 *
+"#;
+        let game = PgnGame::parse(pgn).unwrap();
+
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(
+            game.board().unwrap().to_fen(),
+            "8/3k4/8/4P3/8/8/8/4K3 b - - 0 41"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            PgnGame::parse("1. e4 e5 2. Ke3 *"),
+            Err(PgnError::Move { line: 1, .. })
+        ));
+        assert!(matches!(
+            PgnGame::parse("[Event \"?\"]\n\n1. e4 (1. d4 *"),
+            Err(PgnError::Syntax { line: 3, .. })
+        ));
+        assert!(matches!(
+            PgnGame::parse("1. e4 { never closed"),
+            Err(PgnError::Syntax { line: 1, .. })
+        ));
+        assert!(matches!(
+            PgnGame::parse("[FEN \"8/8 w - - 0 1\"]\n\n*"),
+            Err(PgnError::Fen(FenError::WrongRankCount(2)))
+        ));
+        assert!(matches!(
+            PgnGame::parse("1. e4 e5 1-0 2. Nf3"),
+            Err(PgnError::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn reader() {
+        let pgn = format!(
+            "{}\n{}\n[Event \"third\"]\n\n1. d4 {{ a comment\n[with a bracket] }} d5 1/2-1/2\n",
+            IMMORTAL, "[Event \"second\"]\n\n1. e4 e5 0-1\n"
+        );
+
+        let games = PgnReader::new(pgn.as_bytes())
+            .collect::<Result<Vec<PgnGame>, PgnError>>()
+            .unwrap();
+
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].moves.len(), 45);
+        assert_eq!(games[1].tag("Event"), Some("second"));
+        assert_eq!(games[1].result, PgnResult::BlackWins);
+        assert_eq!(games[2].tag("Event"), Some("third"));
+        assert_eq!(
+            games[2].moves[0].comments,
+            vec!["a comment [with a bracket]".to_owned()]
+        );
+        assert_eq!(games[2].result, PgnResult::Draw);
+
+        let mut reader = PgnReader::new(
+            "[Event \"?\"]\n\n1. e4 e5\n\n[Event \"?\"]\n\n1. e4 Ke7 2. Ke3 *\n".as_bytes(),
+        );
+        assert_eq!(reader.next().unwrap().unwrap().moves.len(), 2);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(PgnError::Move { line: 7, .. }))
+        ));
+        assert!(reader.next().is_none());
+
+        let pgn = "[Event \"a {b\"]\n[Site \"c \\\" {d\"]\n\n1. e4 e5 1-0\n\n\
+                   [Event \"next\"]\n\n1. d4 d5 0-1\n";
+        let games = PgnReader::new(pgn.as_bytes())
+            .collect::<Result<Vec<PgnGame>, PgnError>>()
+            .unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("Event"), Some("a {b"));
+        assert_eq!(games[0].tag("Site"), Some("c \" {d"));
+        assert_eq!(games[1].tag("Event"), Some("next"));
+
+        // games without tags end at their result, even when on the same line
+        let pgn = "1. e4 e5 {not 1-0 yet} (1... c5 2. Nf3) 2. Nf3 1-0\n\n1. d4 d5 0-1\n\
+                   1. c4 * 1. Nf3 1/2-1/2\n\n1. f3 e5 2. g4 Qh4# 0-1";
+        let games = PgnReader::new(pgn.as_bytes())
+            .collect::<Result<Vec<PgnGame>, PgnError>>()
+            .unwrap();
+        assert_eq!(games.len(), 5);
+        assert_eq!(games[0].moves.len(), 3);
+        assert_eq!(games[0].result, PgnResult::WhiteWins);
+        assert_eq!(games[1].result, PgnResult::BlackWins);
+        assert_eq!(games[2].result, PgnResult::Unknown);
+        assert_eq!(games[3].moves.len(), 1);
+        assert_eq!(games[3].result, PgnResult::Draw);
+        assert_eq!(games[4].moves.len(), 4);
+
+        // errors still point at the right line
+        let mut reader = PgnReader::new("1. e4 1-0\n1. e5 0-1\n".as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(PgnError::Move { line: 2, .. }))
+        ));
+
+        // a comment before the first tags is not a game of its own
+        let pgn = "{file comment}\n[Event \"a\"]\n\n1. e4 e5 1-0\n; the end\n";
+        let games = PgnReader::new(pgn.as_bytes())
+            .collect::<Result<Vec<PgnGame>, PgnError>>()
+            .unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].tag("Event"), Some("a"));
+        assert_eq!(games[0].moves.len(), 2);
+    }
+
+    #[test]
+    fn to_pgn() {
+        let pgn = r#"[White "Someone \"Quoted\""]
+[Black "?"]
+[Annotator "me"]
+
+{Start} 1. e4 $1 e5 {solid} 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) () 2... Nc6 3. Bb5 a6 *"#;
+        let game = PgnGame::parse(pgn).unwrap();
+
+        assert_eq!(
+            game.to_pgn().unwrap(),
+            r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "Someone \"Quoted\""]
+[Black "?"]
+[Result "*"]
+[Annotator "me"]
+
+{Start} 1. e4 $1 e5 {solid} 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) () 2... Nc6
+3. Bb5 a6 *
+"#
+        );
+        assert_eq!(
+            PgnGame::parse(&game.to_pgn().unwrap()).unwrap().moves,
+            game.moves
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let game = PgnGame::parse(IMMORTAL).unwrap();
+        let pgn = game.to_pgn().unwrap();
+
+        assert!(pgn.lines().all(|line| line.len() <= LINE_WIDTH));
+        assert!(pgn.contains("\n\n1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5"));
+        assert!(pgn.ends_with("23. Be7# 1-0\n"));
+        assert_eq!(PgnGame::parse(&pgn).unwrap(), game);
+
+        // a closing brace cannot go in a brace comment
+        let game = PgnGame::parse("1. e4 ; see {this} line\n1... e5 *").unwrap();
+        assert_eq!(game.moves[0].comments, ["see {this} line"]);
+        let pgn = game.to_pgn().unwrap();
+        assert!(pgn.contains("\n1. e4 ;see {this} line\n1... e5 *\n"));
+        assert_eq!(PgnGame::parse(&pgn).unwrap().moves, game.moves);
+    }
+
+    #[test]
+    fn from_game() {
+        let mut game = Game::new();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            let mv = game.board().parse_san(san).unwrap();
+            game.make_move(mv).unwrap();
+        }
+
+        let pgn = PgnGame::from(&game).to_pgn().unwrap();
+        assert!(pgn.contains("[Result \"0-1\"]"));
+        assert!(!pgn.contains("FEN"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40";
+        let mut game = Game::from_board(Board::from_fen(fen).unwrap());
+        game.make_move(game.board().parse_san("Kd7").unwrap())
+            .unwrap();
+
+        let mut pgn_game = PgnGame::from(&game);
+        pgn_game.set_tag("Event", "Endgame practice");
+
+        let pgn = pgn_game.to_pgn().unwrap();
+        assert!(pgn.starts_with("[Event \"Endgame practice\"]\n"));
+        assert!(pgn.contains(&format!(
+            "[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"{}\"]\n",
+            fen
+        )));
+        assert!(pgn.ends_with("\n\n40... Kd7 *\n"));
+        assert_eq!(
+            PgnGame::parse(&pgn).unwrap().board().unwrap().to_fen(),
+            game.board().to_fen()
+        );
+    }
+}
@@ -1,12 +1,24 @@
+use crate::bitboard::{bishop_attacks, queen_attacks, rook_attacks};
 use crate::board::Board;
+use crate::moves::{Move, MoveKind};
 use crate::position::{MoveDirection, Position, XY};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fmt::Display;
 
 pub trait Piece: Display {
+    fn kind(&self) -> PieceKind;
     fn color(&self) -> Color;
     fn position(&self) -> Position;
-    fn moves(&self, board: &Board) -> HashSet<Position>;
+    fn moves(&self, board: &Board) -> HashSet<Move>;
+    /// The squares this piece controls, whether or not it could legally move there.
+    /// Unlike `moves`, this includes squares occupied by pieces of the same color.
+    fn attacks(&self, board: &Board) -> HashSet<Position>;
+}
+
+impl Clone for Box<dyn Piece> {
+    fn clone(&self) -> Self {
+        new_piece(self.kind(), self.color(), self.position())
+    }
 }
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
@@ -15,6 +27,97 @@ pub enum Color {
     White,
 }
 
+impl Color {
+    pub const fn opposite(self) -> Self {
+        match self {
+            Color::Black => Color::White,
+            Color::White => Color::Black,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [
+        PieceKind::Pawn,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::King,
+    ];
+
+    /// The kinds a pawn may turn into on reaching the back rank.
+    pub const PROMOTIONS: [PieceKind; 4] = [
+        PieceKind::Queen,
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Knight,
+    ];
+
+    /// The uppercase letter used for this kind in FEN and algebraic notation.
+    pub const fn letter(self) -> char {
+        match self {
+            PieceKind::Pawn => 'P',
+            PieceKind::Knight => 'N',
+            PieceKind::Bishop => 'B',
+            PieceKind::Rook => 'R',
+            PieceKind::Queen => 'Q',
+            PieceKind::King => 'K',
+        }
+    }
+
+    /// The kind for an uppercase or lowercase piece letter.
+    pub const fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'P' => Some(PieceKind::Pawn),
+            'N' => Some(PieceKind::Knight),
+            'B' => Some(PieceKind::Bishop),
+            'R' => Some(PieceKind::Rook),
+            'Q' => Some(PieceKind::Queen),
+            'K' => Some(PieceKind::King),
+            _ => None,
+        }
+    }
+}
+
+/// Turns destination squares into moves for `piece`, skipping squares held by its own side
+/// and recording whatever sits on the others as captured.
+fn moves_to(
+    piece: &dyn Piece,
+    destinations: impl IntoIterator<Item = Position>,
+    board: &Board,
+) -> HashSet<Move> {
+    destinations
+        .into_iter()
+        .filter_map(|to| match board.piece_at(to) {
+            Some(other) if other.color() == piece.color() => None,
+            Some(other) => Some(Move::new(piece.position(), to).with_captured(other.kind())),
+            None => Some(Move::new(piece.position(), to)),
+        })
+        .collect()
+}
+
+pub fn new_piece(kind: PieceKind, color: Color, position: Position) -> Box<dyn Piece> {
+    match kind {
+        PieceKind::Pawn => Box::new(Pawn::new(color, position)),
+        PieceKind::Knight => Box::new(Knight::new(color, position)),
+        PieceKind::Bishop => Box::new(Bishop::new(color, position)),
+        PieceKind::Rook => Box::new(Rook::new(color, position)),
+        PieceKind::Queen => Box::new(Queen::new(color, position)),
+        PieceKind::King => Box::new(King::new(color, position)),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Pawn {
     color: Color,
@@ -28,6 +131,10 @@ impl Pawn {
 }
 
 impl Piece for Pawn {
+    fn kind(&self) -> PieceKind {
+        PieceKind::Pawn
+    }
+
     fn color(&self) -> Color {
         self.color
     }
@@ -35,26 +142,10 @@ impl Piece for Pawn {
         self.position
     }
 
-    // TODO: add check for en passant
-    fn moves(&self, board: &Board) -> HashSet<Position> {
-        let same_color_piece_positions = board
-            .pieces()
-            .iter()
-            .filter(|piece| piece.color() == self.color())
-            .map(|piece| piece.position())
-            .collect::<HashSet<Position>>();
-
-        let opposite_color_piece_positions = board
-            .pieces()
-            .iter()
-            .filter(|piece| piece.color() != self.color())
-            .map(|piece| piece.position())
-            .collect::<HashSet<Position>>();
-
-        let all_piece_positions: HashSet<Position> = same_color_piece_positions
-            .union(&opposite_color_piece_positions)
-            .copied()
-            .collect();
+    fn moves(&self, board: &Board) -> HashSet<Move> {
+        let same_color_piece_positions = board.bitboards().color(self.color());
+        let opposite_color_piece_positions = board.bitboards().color(self.color().opposite());
+        let all_piece_positions = board.bitboards().occupied();
 
         let in_home_row = matches!(
             (self.color(), self.position().to_xy()),
@@ -63,48 +154,108 @@ impl Piece for Pawn {
 
         let mut all = HashSet::new();
 
+        // only a square the opponent just skipped over can be taken en passant:
+        // rank 6 for white, rank 3 for black
+        let en_passant =
+            board
+                .en_passant()
+                .filter(|position| match (self.color(), position.to_xy()) {
+                    (Color::White, XY::OnBoard(_, y)) => y == 5,
+                    (Color::Black, XY::OnBoard(_, y)) => y == 2,
+                    (_, XY::OffBoard) => false,
+                });
+
         match self.color() {
             Color::Black => {
-                if !all_piece_positions.contains(&self.position().down()) {
+                if self.position().down().is_on_board()
+                    && !all_piece_positions.contains(self.position().down())
+                {
                     all.insert(self.position().down());
                 }
 
                 for position in [self.position().down_left(), self.position().down_right()] {
-                    if opposite_color_piece_positions.contains(&position) {
+                    if opposite_color_piece_positions.contains(position)
+                        || en_passant == Some(position)
+                    {
                         all.insert(position);
                     }
                 }
 
                 if in_home_row
-                    && !all_piece_positions.contains(&self.position().down().down())
-                    && !all_piece_positions.contains(&self.position().down())
+                    && self.position().down().down().is_on_board()
+                    && !all_piece_positions.contains(self.position().down().down())
+                    && !all_piece_positions.contains(self.position().down())
                 {
                     all.insert(self.position().down().down());
                 }
             }
             Color::White => {
-                if !all_piece_positions.contains(&self.position().up()) {
+                if self.position().up().is_on_board()
+                    && !all_piece_positions.contains(self.position().up())
+                {
                     all.insert(self.position().up());
                 }
 
                 for position in [self.position().up_left(), self.position().up_right()] {
-                    if opposite_color_piece_positions.contains(&position) {
+                    if opposite_color_piece_positions.contains(position)
+                        || en_passant == Some(position)
+                    {
                         all.insert(position);
                     }
                 }
 
                 if in_home_row
-                    && !all_piece_positions.contains(&self.position().up().up())
-                    && !all_piece_positions.contains(&self.position().up())
+                    && self.position().up().up().is_on_board()
+                    && !all_piece_positions.contains(self.position().up().up())
+                    && !all_piece_positions.contains(self.position().up())
                 {
                     all.insert(self.position().up().up());
                 }
             }
         };
 
-        all.iter()
-            .filter(|position| !same_color_piece_positions.contains(position))
-            .copied()
+        let promotion_row = match self.color() {
+            Color::Black => 0,
+            Color::White => 7,
+        };
+
+        all.into_iter()
+            .filter(|position| !same_color_piece_positions.contains(*position))
+            .flat_map(|to| {
+                let mv = Move::new(self.position(), to);
+                let mv = match (board.piece_at(to), self.position().to_xy(), to.to_xy()) {
+                    (Some(piece), _, _) => mv.with_captured(piece.kind()),
+                    (None, _, _) if en_passant == Some(to) => mv
+                        .with_kind(MoveKind::EnPassant)
+                        .with_captured(PieceKind::Pawn),
+                    (None, XY::OnBoard(_, from_y), XY::OnBoard(_, to_y))
+                        if from_y.abs_diff(to_y) == 2 =>
+                    {
+                        mv.with_kind(MoveKind::DoublePawnPush)
+                    }
+                    (None, _, _) => mv,
+                };
+
+                match to.to_xy() {
+                    XY::OnBoard(_, y) if y == promotion_row => PieceKind::PROMOTIONS
+                        .iter()
+                        .map(|kind| mv.with_promotion(*kind))
+                        .collect(),
+                    _ => vec![mv],
+                }
+            })
+            .collect()
+    }
+
+    fn attacks(&self, _board: &Board) -> HashSet<Position> {
+        let diagonals = match self.color() {
+            Color::Black => [self.position().down_left(), self.position().down_right()],
+            Color::White => [self.position().up_left(), self.position().up_right()],
+        };
+
+        diagonals
+            .into_iter()
+            .filter(|position| position.is_on_board())
             .collect()
     }
 }
@@ -133,6 +284,10 @@ impl Knight {
 }
 
 impl Piece for Knight {
+    fn kind(&self) -> PieceKind {
+        PieceKind::Knight
+    }
+
     fn color(&self) -> Color {
         self.color
     }
@@ -141,14 +296,11 @@ impl Piece for Knight {
         self.position
     }
 
-    fn moves(&self, board: &Board) -> HashSet<Position> {
-        let same_color_piece_positions = board
-            .pieces()
-            .iter()
-            .filter(|piece| piece.color() == self.color())
-            .map(|piece| piece.position())
-            .collect::<HashSet<Position>>();
+    fn moves(&self, board: &Board) -> HashSet<Move> {
+        moves_to(self, self.attacks(board), board)
+    }
 
+    fn attacks(&self, _board: &Board) -> HashSet<Position> {
         [
             Position::compose([MoveDirection::Up, MoveDirection::Up, MoveDirection::Right]),
             Position::compose([MoveDirection::Up, MoveDirection::Up, MoveDirection::Left]),
@@ -182,7 +334,6 @@ impl Piece for Knight {
         .iter()
         .map(|this_move| this_move(self.position()))
         .filter(|position| position.is_on_board())
-        .filter(|position| !same_color_piece_positions.contains(position))
         .collect()
     }
 }
@@ -211,6 +362,10 @@ impl Bishop {
 }
 
 impl Piece for Bishop {
+    fn kind(&self) -> PieceKind {
+        PieceKind::Bishop
+    }
+
     fn color(&self) -> Color {
         self.color
     }
@@ -219,41 +374,12 @@ impl Piece for Bishop {
         self.position
     }
 
-    fn moves(&self, board: &Board) -> HashSet<Position> {
-        let pieces_map: HashMap<Position, &Box<dyn Piece>> = board
-            .pieces()
-            .iter()
-            .map(|piece| (piece.position(), piece))
-            .collect();
-
-        let mut moves = HashSet::new();
-
-        for move_direction in [
-            MoveDirection::UpLeft,
-            MoveDirection::UpRight,
-            MoveDirection::DownRight,
-            MoveDirection::DownLeft,
-        ] {
-            for (position, maybe_piece) in self
-                .position()
-                .stream(move_direction)
-                .take_while(|position| position.is_on_board())
-                .map(|position| (position, pieces_map.get(&position)))
-            {
-                if let Some(piece) = maybe_piece {
-                    if piece.color() == self.color() {
-                    } else {
-                        moves.insert(position);
-                    }
-
-                    break;
-                } else {
-                    moves.insert(position);
-                }
-            }
-        }
+    fn moves(&self, board: &Board) -> HashSet<Move> {
+        moves_to(self, self.attacks(board), board)
+    }
 
-        moves
+    fn attacks(&self, board: &Board) -> HashSet<Position> {
+        bishop_attacks(self.position(), board.bitboards().occupied()).collect()
     }
 }
 
@@ -281,6 +407,10 @@ impl Rook {
 }
 
 impl Piece for Rook {
+    fn kind(&self) -> PieceKind {
+        PieceKind::Rook
+    }
+
     fn color(&self) -> Color {
         self.color
     }
@@ -289,41 +419,12 @@ impl Piece for Rook {
         self.position
     }
 
-    fn moves(&self, board: &Board) -> HashSet<Position> {
-        let pieces_map: HashMap<Position, &Box<dyn Piece>> = board
-            .pieces()
-            .iter()
-            .map(|piece| (piece.position(), piece))
-            .collect();
-
-        let mut moves = HashSet::new();
-
-        for move_direction in [
-            MoveDirection::Up,
-            MoveDirection::Right,
-            MoveDirection::Down,
-            MoveDirection::Left,
-        ] {
-            for (position, maybe_piece) in self
-                .position()
-                .stream(move_direction)
-                .take_while(|position| position.is_on_board())
-                .map(|position| (position, pieces_map.get(&position)))
-            {
-                if let Some(piece) = maybe_piece {
-                    if piece.color() == self.color() {
-                    } else {
-                        moves.insert(position);
-                    }
-
-                    break;
-                } else {
-                    moves.insert(position);
-                }
-            }
-        }
+    fn moves(&self, board: &Board) -> HashSet<Move> {
+        moves_to(self, self.attacks(board), board)
+    }
 
-        moves
+    fn attacks(&self, board: &Board) -> HashSet<Position> {
+        rook_attacks(self.position(), board.bitboards().occupied()).collect()
     }
 }
 
@@ -351,6 +452,10 @@ impl Queen {
 }
 
 impl Piece for Queen {
+    fn kind(&self) -> PieceKind {
+        PieceKind::Queen
+    }
+
     fn color(&self) -> Color {
         self.color
     }
@@ -359,45 +464,12 @@ impl Piece for Queen {
         self.position
     }
 
-    fn moves(&self, board: &Board) -> HashSet<Position> {
-        let pieces_map: HashMap<Position, &Box<dyn Piece>> = board
-            .pieces()
-            .iter()
-            .map(|piece| (piece.position(), piece))
-            .collect();
-
-        let mut moves = HashSet::new();
-
-        for move_direction in [
-            MoveDirection::Up,
-            MoveDirection::Right,
-            MoveDirection::Down,
-            MoveDirection::Left,
-            MoveDirection::UpLeft,
-            MoveDirection::UpRight,
-            MoveDirection::DownRight,
-            MoveDirection::DownLeft,
-        ] {
-            for (position, maybe_piece) in self
-                .position()
-                .stream(move_direction)
-                .take_while(|position| position.is_on_board())
-                .map(|position| (position, pieces_map.get(&position)))
-            {
-                if let Some(piece) = maybe_piece {
-                    if piece.color() == self.color() {
-                    } else {
-                        moves.insert(position);
-                    }
-
-                    break;
-                } else {
-                    moves.insert(position);
-                }
-            }
-        }
+    fn moves(&self, board: &Board) -> HashSet<Move> {
+        moves_to(self, self.attacks(board), board)
+    }
 
-        moves
+    fn attacks(&self, board: &Board) -> HashSet<Position> {
+        queen_attacks(self.position(), board.bitboards().occupied()).collect()
     }
 }
 
@@ -422,9 +494,66 @@ impl King {
     pub fn new(color: Color, position: Position) -> Self {
         Self { color, position }
     }
+
+    /// The castling moves allowed by the board's castling rights.
+    /// The squares between king and rook must be empty, and the king may not
+    /// start on, pass through or land on an attacked square.
+    fn castling_moves(&self, board: &Board) -> Vec<Move> {
+        let home_row = match self.color() {
+            Color::Black => 7,
+            Color::White => 0,
+        };
+
+        if self.position() != Position::new(4, home_row) {
+            return vec![];
+        }
+
+        let castling_rights = board.castling_rights();
+        let opponent = self.color().opposite();
+
+        let is_empty = |x: u8| board.piece_at(Position::new(x, home_row)).is_none();
+        let is_safe = |x: u8| !board.is_attacked(Position::new(x, home_row), opponent);
+        let has_rook = |x: u8| {
+            board
+                .piece_at(Position::new(x, home_row))
+                .is_some_and(|piece| {
+                    piece.kind() == PieceKind::Rook && piece.color() == self.color()
+                })
+        };
+
+        let mut moves = vec![];
+
+        if castling_rights.king_side(self.color())
+            && has_rook(7)
+            && [5, 6].into_iter().all(is_empty)
+            && [4, 5, 6].into_iter().all(is_safe)
+        {
+            moves.push(
+                Move::new(self.position(), Position::new(6, home_row))
+                    .with_kind(MoveKind::KingSideCastle),
+            );
+        }
+
+        if castling_rights.queen_side(self.color())
+            && has_rook(0)
+            && [1, 2, 3].into_iter().all(is_empty)
+            && [4, 3, 2].into_iter().all(is_safe)
+        {
+            moves.push(
+                Move::new(self.position(), Position::new(2, home_row))
+                    .with_kind(MoveKind::QueenSideCastle),
+            );
+        }
+
+        moves
+    }
 }
 
 impl Piece for King {
+    fn kind(&self) -> PieceKind {
+        PieceKind::King
+    }
+
     fn color(&self) -> Color {
         self.color
     }
@@ -433,16 +562,16 @@ impl Piece for King {
         self.position
     }
 
-    // TODO: add check for moving into check
-    // TODO: add castling
-    fn moves(&self, board: &Board) -> HashSet<Position> {
-        let same_color_piece_positions = board
-            .pieces()
-            .iter()
-            .filter(|piece| piece.color() == self.color())
-            .map(|piece| piece.position())
-            .collect::<HashSet<Position>>();
+    // moving into check is filtered out by `Board::legal_moves`
+    fn moves(&self, board: &Board) -> HashSet<Move> {
+        let mut moves = moves_to(self, self.attacks(board), board);
+
+        moves.extend(self.castling_moves(board));
 
+        moves
+    }
+
+    fn attacks(&self, _board: &Board) -> HashSet<Position> {
         [
             self.position().up(),
             self.position().up_right(),
@@ -455,7 +584,6 @@ impl Piece for King {
         ]
         .into_iter()
         .filter(|position| position.is_on_board())
-        .filter(|position| !same_color_piece_positions.contains(position))
         .collect()
     }
 }
@@ -475,6 +603,10 @@ impl Display for King {
 mod tests {
     use super::*;
 
+    fn destinations(moves: HashSet<Move>) -> HashSet<Position> {
+        moves.into_iter().map(|mv| mv.to()).collect()
+    }
+
     #[test]
     fn display() {
         let expected_black = ["♟", "♞", "♝", "♜", "♛", "♚"];
@@ -529,42 +661,127 @@ mod tests {
             let board = Board::empty();
             let pawn = Pawn::new(Color::Black, (4, 6).into());
             assert_eq!(
-                pawn.moves(&board),
+                destinations(pawn.moves(&board)),
                 HashSet::from([(4, 5).into(), (4, 4).into()])
             );
 
             let pawn = Pawn::new(Color::White, (4, 6).into());
-            assert_eq!(pawn.moves(&board), HashSet::from([(4, 7).into()]))
+            assert_eq!(
+                destinations(pawn.moves(&board)),
+                HashSet::from([(4, 7).into()])
+            )
+        }
+
+        #[test]
+        fn last_rank() {
+            let board = Board::empty();
+            let pawn = Pawn::new(Color::White, (4, 7).into());
+            assert_eq!(destinations(pawn.moves(&board)), HashSet::new());
+
+            let pawn = Pawn::new(Color::Black, (4, 0).into());
+            assert_eq!(destinations(pawn.moves(&board)), HashSet::new());
         }
 
         #[test]
         fn blocks() {
             let board = Board::new(vec![Box::new(Pawn::new(Color::Black, (4, 5).into()))]);
             let pawn = Pawn::new(Color::Black, (4, 6).into());
-            assert_eq!(pawn.moves(&board), HashSet::new());
+            assert_eq!(destinations(pawn.moves(&board)), HashSet::new());
 
             let board = Board::new(vec![Box::new(Pawn::new(Color::White, (5, 7).into()))]);
             let pawn = Pawn::new(Color::White, (4, 6).into());
-            assert_eq!(pawn.moves(&board), HashSet::from([(4, 7).into()]));
+            assert_eq!(
+                destinations(pawn.moves(&board)),
+                HashSet::from([(4, 7).into()])
+            );
 
             let board = Board::new(vec![Box::new(Pawn::new(Color::White, (4, 3).into()))]);
             let pawn = Pawn::new(Color::White, (4, 1).into());
-            assert_eq!(pawn.moves(&board), HashSet::from([(4, 2).into()]));
+            assert_eq!(
+                destinations(pawn.moves(&board)),
+                HashSet::from([(4, 2).into()])
+            );
         }
 
         #[test]
         fn takes() {
             let board = Board::new(vec![Box::new(Pawn::new(Color::White, (4, 5).into()))]);
             let pawn = Pawn::new(Color::Black, (4, 6).into());
-            assert_eq!(pawn.moves(&board), HashSet::new());
+            assert_eq!(destinations(pawn.moves(&board)), HashSet::new());
 
             let board = Board::new(vec![Box::new(Pawn::new(Color::Black, (5, 7).into()))]);
             let pawn = Pawn::new(Color::White, (4, 6).into());
             assert_eq!(
-                pawn.moves(&board),
+                destinations(pawn.moves(&board)),
                 HashSet::from([(4, 7).into(), (5, 7).into()])
             );
         }
+
+        #[test]
+        fn move_details() {
+            let board = Board::new(vec![
+                Box::new(Knight::new(Color::Black, (3, 2).into())),
+                Box::new(Rook::new(Color::Black, (0, 7).into())),
+            ]);
+
+            let pawn = Pawn::new(Color::White, (4, 1).into());
+            assert_eq!(
+                pawn.moves(&board),
+                HashSet::from([
+                    Move::new((4, 1).into(), (4, 2).into()),
+                    Move::new((4, 1).into(), (4, 3).into()).with_kind(MoveKind::DoublePawnPush),
+                    Move::new((4, 1).into(), (3, 2).into()).with_captured(PieceKind::Knight),
+                ])
+            );
+
+            let pawn = Pawn::new(Color::White, (1, 6).into());
+            assert_eq!(
+                pawn.moves(&board)
+                    .into_iter()
+                    .filter(|mv| mv.captured() == Some(PieceKind::Rook))
+                    .filter_map(|mv| mv.promotion())
+                    .collect::<HashSet<_>>(),
+                HashSet::from(PieceKind::PROMOTIONS)
+            );
+            assert_eq!(pawn.moves(&board).len(), 8);
+        }
+
+        #[test]
+        fn en_passant() {
+            let mut board = Board::new(vec![
+                Box::new(Pawn::new(Color::White, (0, 1).into())),
+                Box::new(Pawn::new(Color::Black, (3, 6).into())),
+            ]);
+            for (from, to) in [((0, 1), (0, 2)), ((3, 6), (3, 4))] {
+                let mv = board.legal_move(from.into(), to.into(), None).unwrap();
+                board.play(mv);
+            }
+
+            let pawn = Pawn::new(Color::White, (4, 4).into());
+            assert_eq!(
+                destinations(pawn.moves(&board)),
+                HashSet::from([(4, 5).into(), (3, 5).into()])
+            );
+
+            assert!(pawn.moves(&board).contains(
+                &Move::new((4, 4).into(), (3, 5).into())
+                    .with_kind(MoveKind::EnPassant)
+                    .with_captured(PieceKind::Pawn)
+            ));
+
+            let pawn = Pawn::new(Color::White, (5, 4).into());
+            assert_eq!(
+                destinations(pawn.moves(&board)),
+                HashSet::from([(5, 5).into()])
+            );
+
+            // a black pawn can't take on a square its own side skipped over
+            let pawn = Pawn::new(Color::Black, (2, 6).into());
+            assert_eq!(
+                destinations(pawn.moves(&board)),
+                HashSet::from([(2, 5).into(), (2, 4).into()])
+            );
+        }
     }
 
     mod knight {
@@ -575,7 +792,7 @@ mod tests {
             let board = Board::empty();
             let knight = Knight::new(Color::Black, Position::new(4, 4));
             assert_eq!(
-                knight.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(knight.moves(&board)),
                 HashSet::from([
                     // up
                     (5, 6).into(),
@@ -604,7 +821,7 @@ mod tests {
             let knight = Knight::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                knight.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(knight.moves(&board)),
                 HashSet::from([
                     (5, 6).into(),
                     (3, 6).into(),
@@ -627,7 +844,7 @@ mod tests {
             let knight = Knight::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                knight.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(knight.moves(&board)),
                 HashSet::from([
                     (5, 6).into(),
                     (3, 6).into(),
@@ -652,7 +869,7 @@ mod tests {
             let bishop = Bishop::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                bishop.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(bishop.moves(&board)),
                 HashSet::from([
                     (5, 5).into(),
                     (6, 6).into(),
@@ -684,7 +901,7 @@ mod tests {
             let bishop = Bishop::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                bishop.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(bishop.moves(&board)),
                 HashSet::from([
                     (5, 5).into(),
                     (6, 6).into(),
@@ -713,7 +930,7 @@ mod tests {
             let bishop = Bishop::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                bishop.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(bishop.moves(&board)),
                 HashSet::from([
                     (5, 5).into(),
                     (6, 6).into(),
@@ -744,7 +961,7 @@ mod tests {
             let rook = Rook::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                rook.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(rook.moves(&board)),
                 HashSet::from([
                     // up
                     (4, 5).into(),
@@ -777,7 +994,7 @@ mod tests {
             let rook = Rook::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                rook.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(rook.moves(&board)),
                 HashSet::from([
                     // up
                     // right
@@ -805,7 +1022,7 @@ mod tests {
             let rook = Rook::new(Color::Black, Position::new(4, 4));
 
             assert_eq!(
-                rook.moves(&board).into_iter().collect::<HashSet<_>>(),
+                destinations(rook.moves(&board)),
                 HashSet::from([
                     // up
                     // right
@@ -829,7 +1046,7 @@ mod tests {
             let board = Board::empty();
             let queen = Queen::new(Color::Black, (4, 4).into());
             assert_eq!(
-                queen.moves(&board),
+                destinations(queen.moves(&board)),
                 HashSet::from([
                     (4, 5).into(),
                     (4, 6).into(),
@@ -874,7 +1091,7 @@ mod tests {
             let board = Board::new(vec![Box::new(Pawn::new(Color::Black, (4, 5).into()))]);
             let queen = Queen::new(Color::Black, (4, 4).into());
             assert_eq!(
-                queen.moves(&board),
+                destinations(queen.moves(&board)),
                 HashSet::from([
                     // up
                     // right
@@ -922,7 +1139,7 @@ mod tests {
             let queen = Queen::new(Color::Black, (4, 4).into());
 
             assert_eq!(
-                queen.moves(&board),
+                destinations(queen.moves(&board)),
                 HashSet::from([
                     // up
                     // right
@@ -960,6 +1177,7 @@ mod tests {
     }
     mod king {
         use super::*;
+        use crate::board::CastlingRights;
         use crate::piece::Piece;
 
         #[test]
@@ -967,7 +1185,7 @@ mod tests {
             let board = Board::empty();
             let king = King::new(Color::Black, Position::new(4, 4));
             assert_eq!(
-                king.moves(&board),
+                destinations(king.moves(&board)),
                 HashSet::from([
                     (4, 5).into(),
                     (5, 5).into(),
@@ -991,7 +1209,7 @@ mod tests {
             let king = King::new(Color::Black, (4, 4).into());
 
             assert_eq!(
-                king.moves(&board),
+                destinations(king.moves(&board)),
                 HashSet::from([
                     (5, 5).into(),
                     (5, 4).into(),
@@ -1013,7 +1231,7 @@ mod tests {
             let king = King::new(Color::Black, (4, 4).into());
 
             assert_eq!(
-                king.moves(&board),
+                destinations(king.moves(&board)),
                 HashSet::from([
                     (4, 5).into(),
                     (5, 5).into(),
@@ -1026,5 +1244,67 @@ mod tests {
                 ])
             )
         }
+
+        #[test]
+        fn castles() {
+            let mut board = Board::new(vec![
+                Box::new(Rook::new(Color::White, (0, 0).into())),
+                Box::new(Rook::new(Color::White, (7, 0).into())),
+                Box::new(Rook::new(Color::Black, (0, 7).into())),
+                Box::new(Rook::new(Color::Black, (7, 7).into())),
+            ]);
+            board.set_castling_rights(CastlingRights::all());
+
+            let king = King::new(Color::White, (4, 0).into());
+            assert_eq!(
+                destinations(king.moves(&board)),
+                HashSet::from([
+                    (3, 0).into(),
+                    (3, 1).into(),
+                    (4, 1).into(),
+                    (5, 1).into(),
+                    (5, 0).into(),
+                    (6, 0).into(),
+                    (2, 0).into(),
+                ])
+            );
+
+            assert!(king.moves(&board).contains(
+                &Move::new((4, 0).into(), (6, 0).into()).with_kind(MoveKind::KingSideCastle)
+            ));
+            assert!(king.moves(&board).contains(
+                &Move::new((4, 0).into(), (2, 0).into()).with_kind(MoveKind::QueenSideCastle)
+            ));
+
+            board.set_castling_rights(CastlingRights {
+                white_queen_side: false,
+                ..CastlingRights::all()
+            });
+            assert!(!destinations(king.moves(&board)).contains(&(2, 0).into()));
+            assert!(destinations(king.moves(&board)).contains(&(6, 0).into()));
+
+            let king = King::new(Color::White, (4, 1).into());
+            assert!(!destinations(king.moves(&board)).contains(&(6, 1).into()));
+        }
+
+        #[test]
+        fn castling_blocked() {
+            let mut board = Board::new(vec![
+                Box::new(Rook::new(Color::Black, (0, 0).into())),
+                Box::new(Knight::new(Color::Black, (1, 7).into())),
+                Box::new(Rook::new(Color::Black, (7, 7).into())),
+                Box::new(Bishop::new(Color::White, (2, 4).into())),
+            ]);
+            board.set_castling_rights(CastlingRights::all());
+
+            // the queen-side rook is on the wrong side, b8 is occupied, and f8 is attacked
+            let king = King::new(Color::Black, (4, 7).into());
+            assert_eq!(
+                destinations(king.moves(&board))
+                    .intersection(&HashSet::from([(2, 7).into(), (6, 7).into()]))
+                    .count(),
+                0
+            );
+        }
     }
 }
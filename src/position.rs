@@ -1,4 +1,7 @@
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Position {
     repr: Repr,
 }
@@ -166,6 +169,41 @@ impl Position {
         }
     }
 
+    /// The file index, 0 for the a-file through 7 for the h-file.
+    pub const fn file(&self) -> Option<u8> {
+        match self.to_xy() {
+            XY::OffBoard => None,
+            XY::OnBoard(x, _) => Some(x),
+        }
+    }
+
+    /// The rank index, 0 for the first rank through 7 for the eighth.
+    pub const fn rank(&self) -> Option<u8> {
+        match self.to_xy() {
+            XY::OffBoard => None,
+            XY::OnBoard(_, y) => Some(y),
+        }
+    }
+
+    /// The square index, 0 for a1 through 63 for h8, which is also the square's bit
+    /// in a `Bitboard`.
+    pub const fn index(&self) -> Option<u8> {
+        match self.repr {
+            Repr::OffBoard => None,
+            Repr::OnBoard(repr) => Some(repr),
+        }
+    }
+
+    pub const fn from_index(index: u8) -> Self {
+        if index > 63 {
+            Position::off_board()
+        } else {
+            Position {
+                repr: Repr::OnBoard(index),
+            }
+        }
+    }
+
     #[inline]
     pub const fn is_on_board(&self) -> bool {
         match self.repr {
@@ -182,6 +220,48 @@ impl Position {
     }
 }
 
+/// Square names like "e4"; positions off the board are written as "-".
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_xy() {
+            XY::OffBoard => write!(f, "-"),
+            XY::OnBoard(x, y) => write!(f, "{}{}", (b'a' + x) as char, y + 1),
+        }
+    }
+}
+
+impl Debug for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Position({})", self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParsePositionError(String);
+
+impl Display for ParsePositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" is not a square name", self.0)
+    }
+}
+
+impl std::error::Error for ParsePositionError {}
+
+impl FromStr for Position {
+    type Err = ParsePositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => {
+                Ok(Position::new(file as u8 - b'a', rank as u8 - b'1'))
+            }
+            _ => Err(ParsePositionError(s.to_owned())),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum XY {
     OffBoard,
@@ -282,4 +362,31 @@ mod tests {
             expected
         )
     }
+
+    #[test]
+    fn names() {
+        assert_eq!(Position::new(0, 0).to_string(), "a1");
+        assert_eq!(Position::new(4, 3).to_string(), "e4");
+        assert_eq!(Position::new(7, 7).to_string(), "h8");
+        assert_eq!(Position::new(8, 0).to_string(), "-");
+        assert_eq!(format!("{:?}", Position::new(4, 3)), "Position(e4)");
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let position = Position::new(x, y);
+                assert_eq!(position.to_string().parse(), Ok(position));
+                assert_eq!(position.file(), Some(x));
+                assert_eq!(position.rank(), Some(y));
+            }
+        }
+
+        for name in ["", "e", "e9", "i4", "e44", "E4", "-"] {
+            assert_eq!(
+                name.parse::<Position>(),
+                Err(ParsePositionError(name.to_owned()))
+            );
+        }
+
+        assert_eq!(Position::new(4, 8).file(), None);
+    }
 }
@@ -0,0 +1,340 @@
+use std::fmt::Display;
+
+use crate::board::Board;
+use crate::moves::{Move, MoveKind};
+use crate::piece::PieceKind;
+use crate::position::Position;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SanError {
+    Malformed(String),
+    Illegal(String),
+    Ambiguous(String),
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::Malformed(san) => write!(f, "\"{}\" is not a move in SAN", san),
+            SanError::Illegal(san) => write!(f, "\"{}\" is not a legal move here", san),
+            SanError::Ambiguous(san) => {
+                write!(f, "\"{}\" matches more than one legal move", san)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl Board {
+    /// Writes `mv` in Standard Algebraic Notation, e.g. "Nbd7", "exd5", "O-O" or "e8=Q+".
+    /// `mv` has to be a legal move in this position.
+    pub fn to_san(&self, mv: Move) -> String {
+        let mut san = match mv.kind() {
+            MoveKind::KingSideCastle => "O-O".to_owned(),
+            MoveKind::QueenSideCastle => "O-O-O".to_owned(),
+            _ => self.to_san_without_suffix(mv),
+        };
+
+        let mut after = self.clone();
+        after.play(mv);
+
+        if after.is_checkmate() {
+            san.push('#');
+        } else if after.is_check() {
+            san.push('+');
+        }
+
+        san
+    }
+
+    fn to_san_without_suffix(&self, mv: Move) -> String {
+        let kind = self
+            .piece_at(mv.from())
+            .map(|piece| piece.kind())
+            .expect("no piece on the square being moved from");
+
+        let mut san = String::new();
+
+        if kind == PieceKind::Pawn {
+            if mv.is_capture() {
+                san.push_str(&file_name(mv.from()));
+            }
+        } else {
+            san.push(kind.letter());
+
+            let others: Vec<Position> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|other| other.to() == mv.to() && other.from() != mv.from())
+                .filter(|other| self.piece_at(other.from()).map(|piece| piece.kind()) == Some(kind))
+                .map(|other| other.from())
+                .collect();
+
+            if !others.is_empty() {
+                let shares_file = others.iter().any(|from| from.file() == mv.from().file());
+                let shares_rank = others.iter().any(|from| from.rank() == mv.from().rank());
+
+                if !shares_file {
+                    san.push_str(&file_name(mv.from()));
+                } else if !shares_rank {
+                    san.push_str(&rank_name(mv.from()));
+                } else {
+                    san.push_str(&mv.from().to_string());
+                }
+            }
+        }
+
+        if mv.is_capture() {
+            san.push('x');
+        }
+
+        san.push_str(&mv.to().to_string());
+
+        if let Some(promotion) = mv.promotion() {
+            san.push('=');
+            san.push(promotion.letter());
+        }
+
+        san
+    }
+
+    /// Finds the legal move written in Standard Algebraic Notation. Check and mate markers
+    /// and annotation glyphs like "!?" are ignored, as is a missing "x" on a capture.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let malformed = || SanError::Malformed(san.to_owned());
+
+        let trimmed = san.trim_end_matches(['+', '#', '!', '?']);
+
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(MoveKind::KingSideCastle),
+            "O-O-O" | "0-0-0" => Some(MoveKind::QueenSideCastle),
+            _ => None,
+        };
+
+        if let Some(castle) = castle {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.kind() == castle)
+                .ok_or_else(|| SanError::Illegal(san.to_owned()));
+        }
+
+        let (kind, rest) = match trimmed.chars().next() {
+            Some(letter @ ('N' | 'B' | 'R' | 'Q' | 'K')) => (
+                PieceKind::from_letter(letter).ok_or_else(malformed)?,
+                &trimmed[1..],
+            ),
+            Some('a'..='h') => (PieceKind::Pawn, trimmed),
+            _ => return Err(malformed()),
+        };
+
+        let (rest, promotion) = match rest.char_indices().last() {
+            Some((index, letter @ ('N' | 'B' | 'R' | 'Q'))) if kind == PieceKind::Pawn => {
+                let promotion = PieceKind::from_letter(letter);
+                (rest[..index].trim_end_matches('='), promotion)
+            }
+            _ => (rest, None),
+        };
+
+        if rest.len() < 2 || !rest.is_ascii() {
+            return Err(malformed());
+        }
+
+        let (qualifiers, to) = rest.split_at(rest.len() - 2);
+        let to: Position = to.parse().map_err(|_| malformed())?;
+
+        let (qualifiers, is_capture) = match qualifiers.strip_suffix('x') {
+            Some(qualifiers) => (qualifiers, true),
+            None => (qualifiers, false),
+        };
+
+        let mut file = None;
+        let mut rank = None;
+
+        for letter in qualifiers.chars() {
+            match letter {
+                'a'..='h' if file.is_none() && rank.is_none() => {
+                    file = Some(letter as u8 - b'a');
+                }
+                '1'..='8' if rank.is_none() => rank = Some(letter as u8 - b'1'),
+                _ => return Err(malformed()),
+            }
+        }
+
+        let candidates: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.to() == to && mv.promotion() == promotion)
+            .filter(|mv| self.piece_at(mv.from()).map(|piece| piece.kind()) == Some(kind))
+            .filter(|mv| file.is_none() || mv.from().file() == file)
+            .filter(|mv| rank.is_none() || mv.from().rank() == rank)
+            .filter(|mv| !is_capture || mv.is_capture())
+            // a pawn only leaves its file to capture, and then the file is always written
+            .filter(|mv| {
+                kind != PieceKind::Pawn || file.is_some() || mv.from().file() == mv.to().file()
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(SanError::Illegal(san.to_owned())),
+            _ => Err(SanError::Ambiguous(san.to_owned())),
+        }
+    }
+}
+
+fn file_name(position: Position) -> String {
+    position.to_string()[..1].to_owned()
+}
+
+fn rank_name(position: Position) -> String {
+    position.to_string()[1..].to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san(fen: &str, from: &str, to: &str, promotion: Option<PieceKind>) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = board
+            .legal_move(from.parse().unwrap(), to.parse().unwrap(), promotion)
+            .unwrap();
+
+        board.to_san(mv)
+    }
+
+    #[test]
+    fn to_san() {
+        let knights = "rnbqkb1r/ppp1pppp/5n2/3p4/2PP4/8/PP2PPPP/RNBQKBNR b KQkq c3 0 3";
+        assert_eq!(san(knights, "b8", "d7", None), "Nbd7");
+        assert_eq!(san(knights, "f6", "d7", None), "Nfd7");
+        assert_eq!(san(knights, "d5", "c4", None), "dxc4");
+
+        let pawns = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        assert_eq!(san(pawns, "e4", "d5", None), "exd5");
+        assert_eq!(san(pawns, "e4", "e5", None), "e5");
+        assert_eq!(san(pawns, "g1", "f3", None), "Nf3");
+
+        let castles = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(castles, "e1", "g1", None), "O-O");
+        assert_eq!(san(castles, "e1", "c1", None), "O-O-O");
+
+        let promotion = "k7/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(promotion, "e7", "e8", Some(PieceKind::Queen)), "e8=Q+");
+        assert_eq!(san(promotion, "e7", "e8", Some(PieceKind::Knight)), "e8=N");
+
+        let scholars = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+        assert_eq!(san(scholars, "h5", "f7", None), "Qxf7#");
+
+        let rooks = "7k/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(san(rooks, "a1", "a3", None), "R1a3");
+        assert_eq!(san(rooks, "a5", "a3", None), "R5a3");
+
+        let queens = "8/8/k7/8/4Q2Q/8/8/K6Q w - - 0 1";
+        assert_eq!(san(queens, "h4", "e1", None), "Qh4e1");
+        assert_eq!(san(queens, "e4", "e1", None), "Qee1");
+        assert_eq!(san(queens, "h1", "e1", None), "Q1e1");
+    }
+
+    #[test]
+    fn parse_san() {
+        let board = Board::default();
+        assert_eq!(
+            board.parse_san("e4"),
+            Ok(board
+                .legal_move("e2".parse().unwrap(), "e4".parse().unwrap(), None)
+                .unwrap())
+        );
+        assert_eq!(
+            board.parse_san("Nf3!?").map(|mv| mv.from()),
+            Ok("g1".parse().unwrap())
+        );
+        assert_eq!(board.parse_san("Ke2"), Err(SanError::Illegal("Ke2".into())));
+        assert_eq!(board.parse_san("e5"), Err(SanError::Illegal("e5".into())));
+
+        let board =
+            Board::from_fen("rnbqkb1r/ppp1pppp/5n2/3p4/2PP4/8/PP2PPPP/RNBQKBNR b KQkq c3 0 3")
+                .unwrap();
+        assert_eq!(
+            board.parse_san("Nbd7").map(|mv| mv.from()),
+            Ok("b8".parse().unwrap())
+        );
+        assert_eq!(
+            board.parse_san("N6d7").map(|mv| mv.from()),
+            Ok("f6".parse().unwrap())
+        );
+        assert_eq!(
+            board.parse_san("Nd7"),
+            Err(SanError::Ambiguous("Nd7".into()))
+        );
+        assert_eq!(
+            board.parse_san("Nxd7"),
+            Err(SanError::Illegal("Nxd7".into()))
+        );
+        assert_eq!(
+            board.parse_san("dxc4").map(|mv| mv.captured()),
+            Ok(Some(PieceKind::Pawn))
+        );
+
+        // "e5" is only ever a pawn push, never one of the captures on e5
+        let board = Board::from_fen("4k3/8/8/4n3/3P1P2/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("e5"), Err(SanError::Illegal("e5".into())));
+        for san in ["dxe5", "de5"] {
+            assert_eq!(
+                board.parse_san(san).map(|mv| mv.from()),
+                Ok("d4".parse().unwrap())
+            );
+        }
+        assert_eq!(
+            board.parse_san("fxe5").map(|mv| mv.from()),
+            Ok("f4".parse().unwrap())
+        );
+        let board = Board::from_fen("4k3/8/8/4n3/3P4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("e5"), Err(SanError::Illegal("e5".into())));
+        let board = Board::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2").unwrap();
+        assert_eq!(board.parse_san("e6"), Err(SanError::Illegal("e6".into())));
+        assert_eq!(
+            board.parse_san("dxe6").map(|mv| mv.kind()),
+            Ok(MoveKind::EnPassant)
+        );
+
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(
+            board.parse_san("O-O").map(|mv| mv.kind()),
+            Ok(MoveKind::KingSideCastle)
+        );
+        assert_eq!(
+            board.parse_san("0-0-0+").map(|mv| mv.kind()),
+            Ok(MoveKind::QueenSideCastle)
+        );
+
+        let board = Board::from_fen("k7/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        for san in ["e8=Q+", "e8Q"] {
+            assert_eq!(
+                board.parse_san(san).map(|mv| mv.promotion()),
+                Ok(Some(PieceKind::Queen))
+            );
+        }
+        assert_eq!(board.parse_san("e8"), Err(SanError::Illegal("e8".into())));
+
+        for san in ["", "Zf3", "N", "Nz9", "exd", "e4e5e6", "Nf3xx"] {
+            assert_eq!(
+                Board::default().parse_san(san),
+                Err(SanError::Malformed(san.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+
+        for mv in board.legal_moves() {
+            assert_eq!(board.parse_san(&board.to_san(mv)), Ok(mv));
+        }
+    }
+}
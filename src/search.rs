@@ -0,0 +1,437 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::board::Board;
+use crate::eval::{evaluate, piece_value};
+use crate::moves::Move;
+
+/// The score of being checkmated right now. Mates further away score a little less,
+/// so that the search prefers the quickest mate and the slowest defeat.
+const MATE: i32 = 1_000_000;
+const INFINITY: i32 = MATE + 1;
+/// Any score closer to `MATE` than this is a forced mate rather than an evaluation.
+const MATE_BOUND: i32 = MATE - 1_000;
+/// Iterative deepening never goes deeper than this.
+const MAX_DEPTH: u32 = 64;
+/// The depth searched when no limit is set at all.
+const DEFAULT_DEPTH: u32 = 4;
+/// How many nodes go by between looks at the clock.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// When to stop searching. Limits that are `None` do not apply, and the search stops at
+/// whichever of the others is reached first. The first iteration, at depth 1, is always
+/// finished so that there is a move to play.
+///
+/// With no limit set, as in `Limits::default()`, the search stops at depth 4 rather than
+/// running for as long as the deepest iterations take.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// How good a position is for the side to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    /// The evaluation in hundredths of a pawn.
+    Centipawns(i32),
+    /// The side to move mates in this many moves, or is mated in as many if negative.
+    Mate(i32),
+}
+
+impl Score {
+    fn from_search(score: i32) -> Self {
+        if score > MATE_BOUND {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score < -MATE_BOUND {
+            Score::Mate(-(MATE + score) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+/// Written the way UCI engines report scores, e.g. "cp 35" or "mate -2".
+impl Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Score::Centipawns(centipawns) => write!(f, "cp {}", centipawns),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+    /// `None` only if the side to move has no legal moves.
+    pub best_move: Option<Move>,
+    pub score: Score,
+    /// The moves both sides are expected to play, starting with `best_move`.
+    pub pv: Vec<Move>,
+    /// The depth of the last iteration that was searched to the end.
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Searches `board` with iterative deepening until one of `limits` is reached, and
+/// returns the best move found with its score and principal variation.
+pub fn best_move(board: &Board, limits: Limits) -> SearchResult {
+    let mut search = Search {
+        board: board.clone(),
+        limits,
+        start: Instant::now(),
+        nodes: 0,
+        stopped: false,
+        path: vec![],
+        line: vec![],
+        pv: vec![],
+        previous_pv: vec![],
+    };
+
+    let mut result = SearchResult {
+        best_move: None,
+        score: Score::Centipawns(0),
+        pv: vec![],
+        depth: 0,
+        nodes: 0,
+    };
+
+    let max_depth = match limits {
+        Limits {
+            depth: None,
+            nodes: None,
+            time: None,
+        } => DEFAULT_DEPTH,
+        _ => limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH),
+    };
+
+    for depth in 1..=max_depth {
+        let score = search.negamax(depth, 0, -INFINITY, INFINITY);
+
+        if search.stopped {
+            break;
+        }
+
+        search.previous_pv = search.pv[0].clone();
+        result = SearchResult {
+            best_move: search.pv[0].first().copied(),
+            score: Score::from_search(score),
+            pv: search.pv[0].clone(),
+            depth,
+            nodes: search.nodes,
+        };
+
+        // searching deeper cannot find anything better than a forced mate
+        if score.abs() > MATE_BOUND {
+            break;
+        }
+    }
+
+    result.nodes = search.nodes;
+
+    result
+}
+
+struct Search {
+    board: Board,
+    limits: Limits,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    /// The hashes of the positions between the root and the current node, to spot
+    /// repetitions.
+    path: Vec<u64>,
+    /// The moves played from the root to the current node.
+    line: Vec<Move>,
+    /// The best line found from each ply onward, so that `pv[0]` is the principal
+    /// variation once the root has been searched.
+    pv: Vec<Vec<Move>>,
+    /// The principal variation of the previous iteration, searched first.
+    previous_pv: Vec<Move>,
+}
+
+impl Search {
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.pv.len() <= ply + 1 {
+            self.pv.resize(ply + 2, vec![]);
+        }
+        self.pv[ply].clear();
+
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+
+        if depth == 0 {
+            return self.quiescence(alpha, beta);
+        }
+
+        let moves = self.ordered_moves(self.board.legal_moves());
+
+        if moves.is_empty() {
+            return if self.board.is_check() {
+                -MATE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        let mut best = -INFINITY;
+
+        for mv in moves {
+            self.path.push(self.board.hash());
+            self.line.push(mv);
+            let undo = self.board.make_move(mv);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.board.unmake_move(undo);
+            self.line.pop();
+            self.path.pop();
+
+            if self.stopped {
+                return 0;
+            }
+
+            best = best.max(score);
+
+            if score > alpha {
+                alpha = score;
+
+                let line = std::mem::take(&mut self.pv[ply + 1]);
+                self.pv[ply].clear();
+                self.pv[ply].push(mv);
+                self.pv[ply].extend(&line);
+                self.pv[ply + 1] = line;
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+
+    /// Searches captures only, until the position is quiet enough for the evaluation
+    /// to be trusted. The side to move may always stop capturing, so the evaluation
+    /// is a lower bound.
+    fn quiescence(&mut self, mut alpha: i32, beta: i32) -> i32 {
+        let stand_pat = evaluate(&self.board);
+
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let captures = self
+            .board
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.is_capture() || mv.promotion().is_some())
+            .collect();
+
+        let mut best = stand_pat;
+
+        for mv in self.ordered_moves(captures) {
+            if self.should_stop() {
+                return 0;
+            }
+            self.nodes += 1;
+
+            let undo = self.board.make_move(mv);
+            let score = -self.quiescence(-beta, -alpha);
+            self.board.unmake_move(undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+
+    /// Puts the move from the previous principal variation first, then captures of the
+    /// most valuable pieces by the least valuable ones, then everything else.
+    fn ordered_moves(&self, mut moves: Vec<Move>) -> Vec<Move> {
+        // only while the moves so far are the ones the previous iteration expected
+        let pv_move = match self.previous_pv.strip_prefix(self.line.as_slice()) {
+            Some([next, ..]) => Some(*next),
+            _ => None,
+        };
+
+        moves.sort_by_cached_key(|mv| {
+            if Some(*mv) == pv_move {
+                return i32::MIN;
+            }
+
+            let victim = mv.captured().map_or(0, piece_value);
+            let attacker = self
+                .board
+                .piece_at(mv.from())
+                .map_or(0, |piece| piece_value(piece.kind()));
+            let promotion = mv.promotion().map_or(0, piece_value);
+
+            if victim > 0 || promotion > 0 {
+                -(victim * 10 + promotion * 10 - attacker)
+            } else {
+                0
+            }
+        });
+
+        moves
+    }
+
+    /// Draws by repetition within the search, the fifty-move rule or insufficient
+    /// material. A position seen once before on the way here counts as a repetition,
+    /// as repeating it was just as possible the first time.
+    fn is_draw(&self) -> bool {
+        let hash = self.board.hash();
+        let reversible = self.board.halfmove_clock() as usize;
+
+        self.board.halfmove_clock() >= 100
+            || self.board.has_insufficient_material()
+            || self
+                .path
+                .iter()
+                .rev()
+                .take(reversible)
+                .any(|seen| *seen == hash)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        // the first iteration always finishes
+        if self.previous_pv.is_empty() {
+            return false;
+        }
+
+        let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+        let out_of_time = self.nodes.is_multiple_of(CLOCK_INTERVAL)
+            && self
+                .limits
+                .time
+                .is_some_and(|time| self.start.elapsed() >= time);
+
+        self.stopped = out_of_nodes || out_of_time;
+        self.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(depth: u32) -> Limits {
+        Limits {
+            depth: Some(depth),
+            ..Limits::default()
+        }
+    }
+
+    #[test]
+    fn finds_mate() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = best_move(&board, depth(3));
+
+        assert_eq!(
+            result.best_move.map(|mv| board.to_san(mv)),
+            Some("Ra8#".to_owned())
+        );
+        assert_eq!(result.score, Score::Mate(1));
+        assert_eq!(result.pv.len(), 1);
+
+        // black to move can only step aside before Ra8#
+        let board = Board::from_fen("7k/R7/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let result = best_move(&board, depth(4));
+        assert_eq!(result.score, Score::Mate(-1));
+        assert_eq!(result.pv.len(), 2);
+
+        let mated = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let result = best_move(&mated, depth(3));
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, Score::Mate(0));
+    }
+
+    #[test]
+    fn wins_material() {
+        // the knight on d5 is loose, but the one on h6 is guarded by a pawn
+        let board = Board::from_fen("4k3/6p1/7n/3n4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let result = best_move(&board, depth(3));
+
+        assert_eq!(
+            result.best_move.map(|mv| board.to_san(mv)),
+            Some("Qxd5".to_owned())
+        );
+        assert!(matches!(result.score, Score::Centipawns(score) if score > 300));
+    }
+
+    #[test]
+    fn principal_variation() {
+        let board = Board::default();
+        let result = best_move(&board, depth(4));
+
+        assert_eq!(result.depth, 4);
+        assert!(!result.pv.is_empty());
+        assert_eq!(result.best_move, result.pv.first().copied());
+
+        let mut replay = board.clone();
+        for mv in &result.pv {
+            assert!(replay.is_legal(*mv), "{} in {:?}", mv, result.pv);
+            replay.make_move(*mv);
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let board = Board::default();
+
+        let result = best_move(
+            &board,
+            Limits {
+                nodes: Some(2_000),
+                ..Limits::default()
+            },
+        );
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 2_000 || result.depth == 1);
+
+        let start = Instant::now();
+        let result = best_move(
+            &board,
+            Limits {
+                time: Some(Duration::from_millis(100)),
+                ..Limits::default()
+            },
+        );
+        assert!(result.best_move.is_some());
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let board = Board::from_fen("4k3/6p1/7n/3n4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let result = best_move(&board, Limits::default());
+        assert_eq!(result.depth, DEFAULT_DEPTH);
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(Score::from_search(35), Score::Centipawns(35));
+        assert_eq!(Score::from_search(MATE - 1), Score::Mate(1));
+        assert_eq!(Score::from_search(MATE - 3), Score::Mate(2));
+        assert_eq!(Score::from_search(-MATE + 2), Score::Mate(-1));
+        assert_eq!(Score::Mate(-1).to_string(), "mate -1");
+        assert_eq!(Score::Centipawns(-20).to_string(), "cp -20");
+    }
+}
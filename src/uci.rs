@@ -0,0 +1,170 @@
+use std::fmt::Display;
+
+use crate::board::Board;
+use crate::moves::{Move, MoveKind};
+use crate::piece::PieceKind;
+use crate::position::{Position, XY};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UciError {
+    Malformed(String),
+    Illegal(String),
+}
+
+impl Display for UciError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciError::Malformed(uci) => write!(f, "\"{}\" is not a move in UCI notation", uci),
+            UciError::Illegal(uci) => write!(f, "\"{}\" is not a legal move here", uci),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+impl Move {
+    /// Long algebraic notation as used by UCI engines, e.g. "e2e4", "b7b8q" or "e1g1"
+    /// for castling.
+    pub fn to_uci(&self) -> String {
+        let mut uci = format!("{}{}", self.from(), self.to());
+
+        if let Some(promotion) = self.promotion() {
+            uci.push(promotion.letter().to_ascii_lowercase());
+        }
+
+        uci
+    }
+
+    /// Like `to_uci`, except that castling is written as the king taking its own rook,
+    /// e.g. "e1h1", which is what UCI engines expect in Chess960 mode.
+    pub fn to_uci_chess960(&self) -> String {
+        match (self.kind(), self.from().to_xy()) {
+            (MoveKind::KingSideCastle, XY::OnBoard(_, y)) => {
+                format!("{}{}", self.from(), Position::new(7, y))
+            }
+            (MoveKind::QueenSideCastle, XY::OnBoard(_, y)) => {
+                format!("{}{}", self.from(), Position::new(0, y))
+            }
+            _ => self.to_uci(),
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
+
+impl Board {
+    /// Finds the legal move written in UCI long algebraic notation. Castling is accepted
+    /// both as the king moving two squares and as the king taking its own rook.
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, UciError> {
+        let malformed = || UciError::Malformed(uci.to_owned());
+
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(malformed());
+        }
+
+        let from: Position = uci[0..2].parse().map_err(|_| malformed())?;
+        let to: Position = uci[2..4].parse().map_err(|_| malformed())?;
+
+        let promotion = match uci[4..].chars().next() {
+            Some(letter @ ('n' | 'b' | 'r' | 'q')) => PieceKind::from_letter(letter),
+            Some(_) => return Err(malformed()),
+            None => None,
+        };
+
+        let is_own = |position: Position, kind: PieceKind| {
+            self.piece_at(position)
+                .is_some_and(|piece| piece.kind() == kind && piece.color() == self.side_to_move())
+        };
+
+        let mv = if is_own(from, PieceKind::King) && is_own(to, PieceKind::Rook) {
+            let castle = if to.file() > from.file() {
+                MoveKind::KingSideCastle
+            } else {
+                MoveKind::QueenSideCastle
+            };
+
+            self.legal_moves()
+                .into_iter()
+                .find(|mv| mv.from() == from && mv.kind() == castle)
+        } else {
+            self.legal_move(from, to, promotion)
+        };
+
+        mv.ok_or_else(|| UciError::Illegal(uci.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_uci() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        let castle = board.parse_san("O-O").unwrap();
+        assert_eq!(castle.to_uci(), "e1g1");
+        assert_eq!(castle.to_uci_chess960(), "e1h1");
+
+        let castle = board.parse_san("O-O-O").unwrap();
+        assert_eq!(castle.to_uci(), "e1c1");
+        assert_eq!(castle.to_uci_chess960(), "e1a1");
+
+        let promotion = board.parse_san("b8=N").unwrap();
+        assert_eq!(promotion.to_uci(), "b7b8n");
+        assert_eq!(promotion.to_uci_chess960(), "b7b8n");
+        assert_eq!(promotion.to_string(), "b7b8n");
+
+        let board = Board::default();
+        assert_eq!(board.parse_san("Nf3").unwrap().to_uci(), "g1f3");
+    }
+
+    #[test]
+    fn parse_uci() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        for uci in ["e1g1", "e1h1"] {
+            assert_eq!(board.parse_uci(uci), Ok(board.parse_san("O-O").unwrap()));
+        }
+        for uci in ["e1c1", "e1a1"] {
+            assert_eq!(board.parse_uci(uci), Ok(board.parse_san("O-O-O").unwrap()));
+        }
+
+        assert_eq!(
+            board.parse_uci("b7c8q"),
+            Err(UciError::Illegal("b7c8q".into()))
+        );
+        assert_eq!(
+            board.parse_uci("b7b8q").map(|mv| mv.promotion()),
+            Ok(Some(PieceKind::Queen))
+        );
+        assert_eq!(
+            board.parse_uci("b7b8"),
+            Err(UciError::Illegal("b7b8".into()))
+        );
+        assert_eq!(
+            board.parse_uci("a1a8").map(|mv| mv.captured()),
+            Ok(Some(PieceKind::Rook))
+        );
+
+        for uci in ["", "e2", "e2e", "e2e9", "b7b8k", "b7b8qq", "0000"] {
+            assert_eq!(board.parse_uci(uci), Err(UciError::Malformed(uci.into())));
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+
+        for mv in board.legal_moves() {
+            assert_eq!(board.parse_uci(&mv.to_uci()), Ok(mv));
+            assert_eq!(board.parse_uci(&mv.to_uci_chess960()), Ok(mv));
+        }
+    }
+}
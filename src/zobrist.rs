@@ -0,0 +1,239 @@
+use crate::bitboard::{color_index, kind_index, pawn_attacks};
+use crate::board::{Board, CastlingRights};
+use crate::piece::{Color, PieceKind};
+use crate::position::Position;
+
+/// The random numbers XORed together to hash a position: one per piece kind, color and
+/// square, one for black to move, one per castling right and one per en passant file.
+struct Keys {
+    pieces: [[[u64; 64]; 6]; 2],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+/// splitmix64, run at compile time so that hashes are the same in every build.
+const fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const KEYS: Keys = {
+    let mut state = 0x5EED;
+
+    let mut pieces = [[[0; 64]; 6]; 2];
+    let mut color = 0;
+    while color < 2 {
+        let mut kind = 0;
+        while kind < 6 {
+            let mut square = 0;
+            while square < 64 {
+                pieces[color][kind][square] = next(&mut state);
+                square += 1;
+            }
+            kind += 1;
+        }
+        color += 1;
+    }
+
+    let black_to_move = next(&mut state);
+
+    let mut castling = [0; 4];
+    let mut right = 0;
+    while right < 4 {
+        castling[right] = next(&mut state);
+        right += 1;
+    }
+
+    let mut en_passant = [0; 8];
+    let mut file = 0;
+    while file < 8 {
+        en_passant[file] = next(&mut state);
+        file += 1;
+    }
+
+    Keys {
+        pieces,
+        black_to_move,
+        castling,
+        en_passant,
+    }
+};
+
+pub(crate) fn piece(kind: PieceKind, color: Color, position: Position) -> u64 {
+    match position.index() {
+        Some(index) => KEYS.pieces[color_index(color)][kind_index(kind)][index as usize],
+        None => 0,
+    }
+}
+
+pub(crate) fn side_to_move(color: Color) -> u64 {
+    match color {
+        Color::Black => KEYS.black_to_move,
+        Color::White => 0,
+    }
+}
+
+pub(crate) fn castling(rights: CastlingRights) -> u64 {
+    [
+        rights.white_king_side,
+        rights.white_queen_side,
+        rights.black_king_side,
+        rights.black_queen_side,
+    ]
+    .into_iter()
+    .zip(KEYS.castling)
+    .filter(|(right, _)| *right)
+    .fold(0, |hash, (_, key)| hash ^ key)
+}
+
+/// The en passant file counts only when the side to move can legally take en passant,
+/// so that positions where the capture is impossible hash the same whether or not the
+/// square was recorded.
+pub(crate) fn en_passant(board: &Board) -> u64 {
+    let Some(position) = board.en_passant() else {
+        return 0;
+    };
+
+    let color = board.side_to_move();
+    let bitboards = board.bitboards();
+    let pushed = match color {
+        Color::White => position.down(),
+        Color::Black => position.up(),
+    };
+
+    // taking removes two pawns from the rank at once, which may expose the king
+    let mut capturers =
+        pawn_attacks(color.opposite(), position) & bitboards.pieces(PieceKind::Pawn, color);
+    let legal = capturers.any(|from| {
+        let mut after = *bitboards;
+        after.remove(from);
+        after.remove(pushed);
+        after.insert(PieceKind::Pawn, color, position);
+
+        after
+            .king_position(color)
+            .is_none_or(|king| !after.is_attacked(king, color.opposite()))
+    });
+
+    match position.file() {
+        Some(file) if legal => KEYS.en_passant[file as usize],
+        _ => 0,
+    }
+}
+
+/// Hashes `board` from scratch. `Board` keeps its hash up to date as moves are made,
+/// so this is only needed when a board is set up.
+pub(crate) fn hash(board: &Board) -> u64 {
+    board
+        .pieces()
+        .iter()
+        .fold(0, |hash, p| hash ^ piece(p.kind(), p.color(), p.position()))
+        ^ side_to_move(board.side_to_move())
+        ^ castling(board.castling_rights())
+        ^ en_passant(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen::STARTING_FEN;
+
+    #[test]
+    fn keys_differ() {
+        let mut keys: Vec<u64> = KEYS.pieces.iter().flatten().flatten().copied().collect();
+        keys.push(KEYS.black_to_move);
+        keys.extend(KEYS.castling);
+        keys.extend(KEYS.en_passant);
+
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+        assert!(keys.iter().all(|key| *key != 0));
+    }
+
+    #[test]
+    fn position_identity() {
+        let start = Board::from_fen(STARTING_FEN).unwrap();
+        assert_eq!(start.hash(), Board::default().hash());
+
+        // the clocks are not part of the position
+        let later =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 8 5").unwrap();
+        assert_eq!(start.hash(), later.hash());
+
+        let black =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(start.hash(), black.hash());
+
+        let no_castling =
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap();
+        assert_ne!(start.hash(), no_castling.hash());
+
+        // after 1. e4 no black pawn can take en passant, so the square is left out
+        let e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq";
+        assert_eq!(
+            Board::from_fen(&format!("{} e3 0 1", e4)).unwrap().hash(),
+            Board::from_fen(&format!("{} - 0 1", e4)).unwrap().hash()
+        );
+
+        let d5 = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq";
+        assert_ne!(
+            Board::from_fen(&format!("{} f6 0 3", d5)).unwrap().hash(),
+            Board::from_fen(&format!("{} - 0 3", d5)).unwrap().hash()
+        );
+    }
+
+    #[test]
+    fn incremental() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let original = board.hash();
+
+            for mv in board.legal_moves() {
+                let undo = board.make_move(mv);
+                assert_eq!(board.hash(), hash(&board), "after {}", mv);
+
+                for reply in board.legal_moves() {
+                    let undo = board.make_move(reply);
+                    assert_eq!(board.hash(), hash(&board), "after {} {}", mv, reply);
+                    board.unmake_move(undo);
+                }
+
+                board.unmake_move(undo);
+                assert_eq!(board.hash(), original);
+            }
+        }
+    }
+
+    #[test]
+    fn transpositions() {
+        let mut one = Board::default();
+        let mut other = Board::default();
+
+        for san in ["Nf3", "Nf6", "Nc3", "Nc6"] {
+            let mv = one.parse_san(san).unwrap();
+            one.make_move(mv);
+        }
+        for san in ["Nc3", "Nc6", "Nf3", "Nf6"] {
+            let mv = other.parse_san(san).unwrap();
+            other.make_move(mv);
+        }
+        assert_eq!(one.hash(), other.hash());
+
+        for san in ["Nb1", "Nb8", "Ng1", "Ng8"] {
+            let mv = one.parse_san(san).unwrap();
+            one.make_move(mv);
+        }
+        assert_eq!(one.hash(), Board::default().hash());
+    }
+}